@@ -0,0 +1,353 @@
+use crate::geometry::distance::cumulative_segments;
+use crate::types::{Coordinate, Route, Track, Waypoint};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClimbOptions {
+    /// Shortest climb reported, in metres.
+    pub min_length: f64,
+    /// Smallest average gradient reported, as rise over run.
+    pub min_grade: f64,
+    /// Smallest elevation gain reported, in metres.
+    pub min_gain: f64,
+    /// A climb ends once the road drops this many metres below its highest
+    /// point so far.
+    pub max_descent: f64,
+    /// Horizontal distance in metres over which the maximum gradient is
+    /// measured.
+    pub grade_window: f64,
+}
+
+impl Default for ClimbOptions {
+    fn default() -> Self {
+        Self {
+            min_length: 500.0,
+            min_grade: 0.03,
+            min_gain: 20.0,
+            max_descent: 10.0,
+            grade_window: 100.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClimbCategory {
+    Cat4,
+    Cat3,
+    Cat2,
+    Cat1,
+    Hc,
+}
+
+impl ClimbCategory {
+    /// Categorises a climb by its score, the length in metres multiplied by
+    /// the average gradient in percent.
+    pub fn from_score(score: f64) -> Option<Self> {
+        match score {
+            s if s >= 80_000.0 => Some(ClimbCategory::Hc),
+            s if s >= 64_000.0 => Some(ClimbCategory::Cat1),
+            s if s >= 32_000.0 => Some(ClimbCategory::Cat2),
+            s if s >= 16_000.0 => Some(ClimbCategory::Cat3),
+            s if s >= 8_000.0 => Some(ClimbCategory::Cat4),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ClimbCategory::Cat4 => "Cat 4",
+            ClimbCategory::Cat3 => "Cat 3",
+            ClimbCategory::Cat2 => "Cat 2",
+            ClimbCategory::Cat1 => "Cat 1",
+            ClimbCategory::Hc => "HC",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Climb {
+    pub track_index: usize,
+    /// Distances along the track in metres.
+    pub start_distance: f64,
+    pub end_distance: f64,
+    pub length: f64,
+    /// Elevation difference between the foot and the summit, in metres.
+    pub gain: f64,
+    pub average_grade: f64,
+    pub max_grade: f64,
+    pub score: f64,
+    pub category: Option<ClimbCategory>,
+    pub start: Coordinate,
+    pub summit: Coordinate,
+}
+
+impl Climb {
+    fn label(&self) -> String {
+        let category = self.category.map_or("Climb", |c| c.label());
+        format!(
+            "{} ({:.1} km at {:.1}%)",
+            category,
+            self.length / 1000.0,
+            self.average_grade * 100.0
+        )
+    }
+}
+
+pub fn detect(route: &Route, options: &ClimbOptions) -> Vec<Climb> {
+    route
+        .tracks
+        .iter()
+        .enumerate()
+        .flat_map(|(i, track)| detect_track(track, i, options))
+        .collect()
+}
+
+/// Finds sustained climbs along a track. Segments are joined end to end,
+/// without counting any gap between them, and points without elevation are
+/// skipped.
+pub fn detect_track(track: &Track, track_index: usize, options: &ClimbOptions) -> Vec<Climb> {
+    let points: Vec<Coordinate> = track
+        .segments
+        .iter()
+        .flat_map(|s| s.points.iter().cloned())
+        .collect();
+    let distances = cumulative_segments(&track.segments);
+    let profile: Vec<(f64, f64, &Coordinate)> = points
+        .iter()
+        .zip(&distances)
+        .filter_map(|(p, &d)| p.ele.map(|e| (d, e, p)))
+        .collect();
+
+    let mut climbs = Vec::new();
+    if profile.is_empty() {
+        return climbs;
+    }
+
+    let mut close = |low: usize, high: usize| {
+        if let Some(climb) = build(&profile[low..=high], track_index, options) {
+            climbs.push(climb);
+        }
+    };
+
+    let (mut low, mut high) = (0, 0);
+    for i in 1..profile.len() {
+        let ele = profile[i].1;
+        if ele <= profile[low].1 {
+            close(low, high);
+            (low, high) = (i, i);
+        } else if ele > profile[high].1 {
+            high = i;
+        } else if profile[high].1 - ele > options.max_descent {
+            close(low, high);
+            (low, high) = (i, i);
+        }
+    }
+    close(low, high);
+
+    climbs
+}
+
+fn build(
+    profile: &[(f64, f64, &Coordinate)],
+    track_index: usize,
+    options: &ClimbOptions,
+) -> Option<Climb> {
+    let (start_distance, start_ele, start) = profile[0];
+    let (end_distance, end_ele, summit) = profile[profile.len() - 1];
+    let length = end_distance - start_distance;
+    let gain = end_ele - start_ele;
+    if length < options.min_length || length <= 0.0 || gain < options.min_gain {
+        return None;
+    }
+
+    let average_grade = gain / length;
+    if average_grade < options.min_grade {
+        return None;
+    }
+
+    let score = length * average_grade * 100.0;
+    Some(Climb {
+        track_index,
+        start_distance,
+        end_distance,
+        length,
+        gain,
+        average_grade,
+        max_grade: max_grade(profile, options.grade_window).max(average_grade),
+        score,
+        category: ClimbCategory::from_score(score),
+        start: start.clone(),
+        summit: summit.clone(),
+    })
+}
+
+fn max_grade(profile: &[(f64, f64, &Coordinate)], window: f64) -> f64 {
+    let mut max = f64::NEG_INFINITY;
+    let mut end = 0;
+
+    for start in 0..profile.len() {
+        while end < profile.len() && profile[end].0 - profile[start].0 < window {
+            end += 1;
+        }
+        if end == profile.len() {
+            break;
+        }
+        let run = profile[end].0 - profile[start].0;
+        max = max.max((profile[end].1 - profile[start].1) / run);
+    }
+
+    max
+}
+
+/// A waypoint at the foot and at the summit of each climb.
+pub fn to_waypoints(climbs: &[Climb]) -> Vec<Waypoint> {
+    climbs
+        .iter()
+        .flat_map(|climb| {
+            let label = climb.label();
+            [
+                Waypoint {
+                    coord: climb.start.clone(),
+                    name: Some(format!("Start: {}", label)),
+                    symbol: Some("Flag, Green".to_string()),
+                    kind: Some("climb_start".to_string()),
+                },
+                Waypoint {
+                    coord: climb.summit.clone(),
+                    name: Some(format!("Summit: {}", label)),
+                    symbol: Some("Summit".to_string()),
+                    kind: Some("climb_summit".to_string()),
+                },
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TrackSegment;
+
+    /// A track heading north with points ~111 m apart at the given
+    /// elevations.
+    fn track(elevations: &[f64]) -> Track {
+        Track::new(vec![TrackSegment::new(
+            elevations
+                .iter()
+                .enumerate()
+                .map(|(i, &e)| Coordinate::with_elevation(i as f64 * 0.001, 0.0, e))
+                .collect(),
+        )])
+    }
+
+    fn ramp(from: f64, step: f64, count: usize) -> Vec<f64> {
+        (0..count).map(|i| from + step * i as f64).collect()
+    }
+
+    #[test]
+    fn test_detect_single_climb() {
+        // 2 km at 5%, between flat sections.
+        let mut elevations = vec![100.0; 5];
+        elevations.extend(ramp(100.0, 5.56, 19).into_iter().skip(1));
+        elevations.extend(vec![elevations[elevations.len() - 1]; 4]);
+
+        let climbs = detect_track(&track(&elevations), 0, &ClimbOptions::default());
+        assert_eq!(climbs.len(), 1);
+        let climb = &climbs[0];
+        assert!((climb.length - 2001.0).abs() < 5.0);
+        assert!((climb.gain - 100.08).abs() < 0.01);
+        assert!((climb.average_grade - 0.05).abs() < 0.001);
+        assert_eq!(climb.category, Some(ClimbCategory::Cat4));
+        assert!((climb.start.lat - 0.004).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_gap_between_segments_is_not_climb_distance() {
+        let whole = track(&ramp(0.0, 5.56, 19));
+        let points = &whole.segments[0].points;
+        // Repeat the middle point after a ~1.1 km jump north.
+        let shifted = points[9..]
+            .iter()
+            .map(|p| Coordinate::with_elevation(p.lat + 0.01, p.lon, p.ele.unwrap()))
+            .collect();
+        let split = Track::new(vec![
+            TrackSegment::new(points[..10].to_vec()),
+            TrackSegment::new(shifted),
+        ]);
+
+        let expected = &detect_track(&whole, 0, &ClimbOptions::default())[0];
+        let climbs = detect_track(&split, 0, &ClimbOptions::default());
+        assert_eq!(climbs.len(), 1);
+        assert!((climbs[0].length - expected.length).abs() < 1e-6);
+        assert!((climbs[0].average_grade - expected.average_grade).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_small_dip_does_not_end_climb() {
+        let mut elevations = ramp(0.0, 10.0, 10);
+        elevations.push(85.0);
+        elevations.extend(ramp(90.0, 10.0, 10));
+
+        let climbs = detect_track(&track(&elevations), 0, &ClimbOptions::default());
+        assert_eq!(climbs.len(), 1);
+        assert!((climbs[0].gain - 180.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_descent_splits_climbs() {
+        let mut elevations = ramp(0.0, 10.0, 10);
+        elevations.extend(ramp(60.0, 10.0, 10));
+
+        let climbs = detect_track(&track(&elevations), 0, &ClimbOptions::default());
+        assert_eq!(climbs.len(), 2);
+        assert_eq!(climbs[1].start.ele, Some(60.0));
+    }
+
+    #[test]
+    fn test_ignores_short_or_shallow_rises() {
+        let options = ClimbOptions::default();
+        assert!(detect_track(&track(&ramp(0.0, 10.0, 4)), 0, &options).is_empty());
+        assert!(detect_track(&track(&ramp(0.0, 2.0, 30)), 0, &options).is_empty());
+        assert!(detect_track(&track(&[]), 0, &options).is_empty());
+    }
+
+    #[test]
+    fn test_max_grade() {
+        let mut elevations = ramp(0.0, 5.0, 10);
+        elevations.extend(ramp(60.0, 15.0, 5));
+
+        let climbs = detect_track(&track(&elevations), 0, &ClimbOptions::default());
+        assert!((climbs[0].max_grade - 15.0 / 111.2).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_categories() {
+        assert_eq!(ClimbCategory::from_score(7_999.0), None);
+        assert_eq!(
+            ClimbCategory::from_score(20_000.0),
+            Some(ClimbCategory::Cat3)
+        );
+        assert_eq!(
+            ClimbCategory::from_score(70_000.0),
+            Some(ClimbCategory::Cat1)
+        );
+        assert_eq!(
+            ClimbCategory::from_score(150_000.0),
+            Some(ClimbCategory::Hc)
+        );
+    }
+
+    #[test]
+    fn test_climb_waypoints() {
+        let climbs = detect_track(&track(&ramp(0.0, 10.0, 20)), 0, &ClimbOptions::default());
+        let waypoints = to_waypoints(&climbs);
+        assert_eq!(waypoints.len(), 2);
+        assert_eq!(
+            waypoints[1].name.as_deref(),
+            Some("Summit: Cat 3 (2.1 km at 9.0%)")
+        );
+        assert_eq!(waypoints[1].symbol.as_deref(), Some("Summit"));
+    }
+}
@@ -0,0 +1,368 @@
+use crate::geometry::distance::{cumulative, haversine};
+use crate::geometry::project::project;
+use crate::types::{Coordinate, Route, Track, TrackSegment};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompareOptions {
+    /// Points further than this many metres from the other route count as
+    /// divergent.
+    pub threshold: f64,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self { threshold: 20.0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    First,
+    Second,
+}
+
+/// A stretch of one route that strays from the other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Divergence {
+    pub side: Side,
+    /// Distances along that route's tracks, in metres.
+    pub start_distance: f64,
+    pub end_distance: f64,
+    /// Furthest distance from the other route within the stretch.
+    pub max_distance: f64,
+    /// The stretch, including the last matching point on either side.
+    pub segment: TrackSegment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comparison {
+    /// Largest distance from any point of either route to the other route.
+    pub hausdorff: f64,
+    /// Discrete Fréchet distance between the two point sequences, which also
+    /// accounts for the order the routes are travelled in.
+    pub frechet: f64,
+    /// Percentage of the first route's length within the threshold of the
+    /// second route.
+    pub first_within: f64,
+    pub second_within: f64,
+    pub divergences: Vec<Divergence>,
+}
+
+/// Compares the track geometry of two routes, taking the segments of each in
+/// order without the gaps between them. Divergence is measured at track
+/// points, so resample sparse routes first for finer results. Returns `None`
+/// when either route has no track points.
+pub fn compare(first: &Route, second: &Route, options: &CompareOptions) -> Option<Comparison> {
+    let a = segments(first);
+    let b = segments(second);
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    let a_off = offsets(&a, &b);
+    let b_off = offsets(&b, &a);
+    let hausdorff = a_off
+        .iter()
+        .chain(&b_off)
+        .flatten()
+        .copied()
+        .fold(0.0, f64::max);
+
+    let mut stretches = divergences(&a, &a_off, Side::First, options.threshold);
+    stretches.extend(divergences(&b, &b_off, Side::Second, options.threshold));
+
+    Some(Comparison {
+        hausdorff,
+        frechet: frechet(&a.concat(), &b.concat()),
+        first_within: within(&a, &a_off, options.threshold),
+        second_within: within(&b, &b_off, options.threshold),
+        divergences: stretches,
+    })
+}
+
+/// The non-empty segments of a route's tracks, in order.
+fn segments(route: &Route) -> Vec<&[Coordinate]> {
+    route
+        .tracks
+        .iter()
+        .flat_map(|t| &t.segments)
+        .map(|s| s.points.as_slice())
+        .filter(|points| !points.is_empty())
+        .collect()
+}
+
+/// Distance from each point of each segment to the closest position on any
+/// segment of `other`, so the gaps between segments are never matched.
+fn offsets(segments: &[&[Coordinate]], other: &[&[Coordinate]]) -> Vec<Vec<f64>> {
+    segments
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|p| {
+                    other
+                        .iter()
+                        .filter_map(|s| project(s, p))
+                        .map(|p| p.distance_off)
+                        .fold(f64::INFINITY, f64::min)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Percentage of the segments' length made of edges with both ends within
+/// `threshold`.
+fn within(segments: &[&[Coordinate]], offsets: &[Vec<f64>], threshold: f64) -> f64 {
+    let mut total = 0.0;
+    let mut matched = 0.0;
+    for (line, offsets) in segments.iter().zip(offsets) {
+        for (i, pair) in line.windows(2).enumerate() {
+            let length = haversine(&pair[0], &pair[1]);
+            total += length;
+            if offsets[i] <= threshold && offsets[i + 1] <= threshold {
+                matched += length;
+            }
+        }
+    }
+
+    if total > 0.0 {
+        matched / total * 100.0
+    } else if offsets[0][0] <= threshold {
+        100.0
+    } else {
+        0.0
+    }
+}
+
+/// Stretches of divergent points, each within one segment. Distances are
+/// measured along the segments without the gaps between them.
+fn divergences(
+    segments: &[&[Coordinate]],
+    offsets: &[Vec<f64>],
+    side: Side,
+    threshold: f64,
+) -> Vec<Divergence> {
+    let mut stretches = Vec::new();
+    let mut along = 0.0;
+
+    for (line, offsets) in segments.iter().zip(offsets) {
+        let distances = cumulative(line);
+        let mut i = 0;
+
+        while i < line.len() {
+            if offsets[i] <= threshold {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < line.len() && offsets[i] > threshold {
+                i += 1;
+            }
+            let (from, to) = (start.saturating_sub(1), i.min(line.len() - 1));
+
+            stretches.push(Divergence {
+                side,
+                start_distance: along + distances[from],
+                end_distance: along + distances[to],
+                max_distance: offsets[start..i].iter().copied().fold(0.0, f64::max),
+                segment: TrackSegment::new(line[from..=to].to_vec()),
+            });
+        }
+
+        along += distances[distances.len() - 1];
+    }
+
+    stretches
+}
+
+/// Discrete Fréchet distance, computed row by row to keep memory linear.
+/// Only points are coupled, so the gaps between segments add nothing.
+fn frechet(a: &[Coordinate], b: &[Coordinate]) -> f64 {
+    let mut previous: Vec<f64> = Vec::with_capacity(b.len());
+    for (j, q) in b.iter().enumerate() {
+        let d = haversine(&a[0], q);
+        previous.push(if j == 0 { d } else { d.max(previous[j - 1]) });
+    }
+
+    for p in &a[1..] {
+        let mut current: Vec<f64> = Vec::with_capacity(b.len());
+        for (j, q) in b.iter().enumerate() {
+            let d = haversine(p, q);
+            let reachable = if j == 0 {
+                previous[0]
+            } else {
+                previous[j].min(previous[j - 1]).min(current[j - 1])
+            };
+            current.push(d.max(reachable));
+        }
+        previous = current;
+    }
+
+    previous[b.len() - 1]
+}
+
+/// Both routes plus one track per divergence, for writing out as GPX or
+/// GeoJSON and viewing on a map.
+pub fn overlay(first: &Route, second: &Route, comparison: &Comparison) -> Route {
+    let mut overlay = Route::with_name("Route comparison".to_string());
+
+    for (route, fallback) in [(first, "First route"), (second, "Second route")] {
+        let segments = route
+            .tracks
+            .iter()
+            .flat_map(|t| t.segments.clone())
+            .collect();
+        let name = route.name.clone().unwrap_or_else(|| fallback.to_string());
+        overlay.add_track(Track::with_name(name, segments));
+    }
+
+    for (i, divergence) in comparison.divergences.iter().enumerate() {
+        let side = match divergence.side {
+            Side::First => "first",
+            Side::Second => "second",
+        };
+        overlay.add_track(Track::with_name(
+            format!(
+                "Divergence {} ({} route, km {:.2}–{:.2}, up to {:.0} m off)",
+                i + 1,
+                side,
+                divergence.start_distance / 1000.0,
+                divergence.end_distance / 1000.0,
+                divergence.max_distance
+            ),
+            vec![divergence.segment.clone()],
+        ));
+    }
+
+    overlay
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(points: &[(f64, f64)]) -> Route {
+        let mut route = Route::new();
+        route.add_track(Track::new(vec![TrackSegment::new(
+            points
+                .iter()
+                .map(|&(lat, lon)| Coordinate::new(lat, lon))
+                .collect(),
+        )]));
+        route
+    }
+
+    fn straight() -> Route {
+        route(
+            &(0..=10)
+                .map(|i| (0.0, i as f64 * 0.001))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn test_identical_routes() {
+        let comparison = compare(&straight(), &straight(), &CompareOptions::default()).unwrap();
+        assert_eq!(comparison.hausdorff, 0.0);
+        assert_eq!(comparison.frechet, 0.0);
+        assert_eq!(comparison.first_within, 100.0);
+        assert!(comparison.divergences.is_empty());
+    }
+
+    #[test]
+    fn test_detour_is_divergent() {
+        let mut points: Vec<(f64, f64)> = (0..=10).map(|i| (0.0, i as f64 * 0.001)).collect();
+        points[4].0 = 0.001;
+        points[5].0 = 0.001;
+        let detour = route(&points);
+
+        let comparison = compare(&straight(), &detour, &CompareOptions::default()).unwrap();
+        assert!((comparison.hausdorff - 111.2).abs() < 0.5);
+        assert!((comparison.first_within - 70.0).abs() < 1e-6);
+        assert!((comparison.second_within - 64.7).abs() < 0.5);
+
+        // The straight route strays from the detour just as much as the
+        // detour strays from it.
+        assert_eq!(comparison.divergences.len(), 2);
+        assert_eq!(comparison.divergences[0].side, Side::First);
+        let divergence = &comparison.divergences[1];
+        assert_eq!(divergence.side, Side::Second);
+        assert_eq!(divergence.segment.points.len(), 4);
+        assert!((divergence.max_distance - 111.2).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_gap_between_segments_is_not_matched() {
+        // Same line as `straight`, but with nothing recorded between
+        // 0.004 and 0.006.
+        let mut gapped = straight();
+        let points = gapped.tracks[0].segments[0].points.clone();
+        gapped.tracks[0].segments = vec![
+            TrackSegment::new(points[..5].to_vec()),
+            TrackSegment::new(points[6..].to_vec()),
+        ];
+
+        let comparison = compare(&gapped, &straight(), &CompareOptions::default()).unwrap();
+        assert_eq!(comparison.first_within, 100.0);
+        assert!((comparison.hausdorff - 111.2).abs() < 0.5);
+
+        assert_eq!(comparison.divergences.len(), 1);
+        let divergence = &comparison.divergences[0];
+        assert_eq!(divergence.side, Side::Second);
+        assert!((divergence.max_distance - 111.2).abs() < 0.5);
+
+        // Distances along the gapped route skip the gap: the detour starts
+        // five steps in, not six.
+        gapped.tracks[0].segments[1].points[2].lat = 0.001;
+        let comparison = compare(&gapped, &straight(), &CompareOptions::default()).unwrap();
+        let divergence = &comparison.divergences[0];
+        assert_eq!(divergence.side, Side::First);
+        let step = haversine(&points[0], &points[1]);
+        assert!((divergence.start_distance - 5.0 * step).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_frechet_detects_reversed_direction() {
+        let reversed = crate::transform::reverse::reverse(&straight());
+        let comparison = compare(&straight(), &reversed, &CompareOptions::default()).unwrap();
+        assert_eq!(comparison.hausdorff, 0.0);
+        assert!(comparison.frechet > 1000.0);
+    }
+
+    #[test]
+    fn test_frechet_of_parallel_lines() {
+        let shifted = route(
+            &(0..=10)
+                .map(|i| (0.0005, i as f64 * 0.001))
+                .collect::<Vec<_>>(),
+        );
+        let comparison = compare(&straight(), &shifted, &CompareOptions::default()).unwrap();
+        assert!((comparison.frechet - 55.6).abs() < 0.5);
+        assert_eq!(comparison.divergences.len(), 2);
+    }
+
+    #[test]
+    fn test_empty_route() {
+        assert!(compare(&straight(), &Route::new(), &CompareOptions::default()).is_none());
+    }
+
+    #[test]
+    fn test_overlay_tracks() {
+        let mut points: Vec<(f64, f64)> = (0..=10).map(|i| (0.0, i as f64 * 0.001)).collect();
+        points[5].0 = 0.001;
+        let detour = route(&points);
+        let comparison = compare(&straight(), &detour, &CompareOptions::default()).unwrap();
+
+        let overlay = overlay(&straight(), &detour, &comparison);
+        assert_eq!(overlay.tracks.len(), 4);
+        assert_eq!(overlay.tracks[0].name.as_deref(), Some("First route"));
+        assert!(overlay.tracks[3]
+            .name
+            .as_deref()
+            .unwrap()
+            .starts_with("Divergence 2 (second route"));
+    }
+}
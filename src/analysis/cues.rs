@@ -0,0 +1,324 @@
+use crate::geometry::distance::{bearing, cumulative, haversine, length, point_at};
+use crate::types::{Coordinate, Route, Waypoint};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CueOptions {
+    /// Smallest change of direction, in degrees, reported as a turn.
+    pub min_angle: f64,
+    /// Turns at least this sharp are reported as sharp turns.
+    pub sharp_angle: f64,
+    /// Turns at least this sharp are reported as U-turns.
+    pub u_turn_angle: f64,
+    /// Distance in metres before and after each point over which the
+    /// incoming and outgoing bearings are measured.
+    pub window: f64,
+    /// Turns closer together than this many metres are merged, keeping the
+    /// sharpest.
+    pub min_spacing: f64,
+}
+
+impl Default for CueOptions {
+    fn default() -> Self {
+        Self {
+            min_angle: 30.0,
+            sharp_angle: 100.0,
+            u_turn_angle: 160.0,
+            window: 25.0,
+            min_spacing: 50.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Turn {
+    Left,
+    Right,
+    SharpLeft,
+    SharpRight,
+    UTurn,
+}
+
+impl Turn {
+    pub fn instruction(self) -> &'static str {
+        match self {
+            Turn::Left => "Turn left",
+            Turn::Right => "Turn right",
+            Turn::SharpLeft => "Sharp left",
+            Turn::SharpRight => "Sharp right",
+            Turn::UTurn => "U-turn",
+        }
+    }
+
+    /// Short label used for GPX `<sym>`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Turn::Left => "Left",
+            Turn::Right => "Right",
+            Turn::SharpLeft => "Sharp Left",
+            Turn::SharpRight => "Sharp Right",
+            Turn::UTurn => "U Turn",
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Turn::Left => "left",
+            Turn::Right => "right",
+            Turn::SharpLeft => "sharp_left",
+            Turn::SharpRight => "sharp_right",
+            Turn::UTurn => "u_turn",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cue {
+    pub turn: Turn,
+    /// Change of direction in degrees, positive to the right.
+    pub angle: f64,
+    /// Distance along the whole route in metres.
+    pub distance: f64,
+    pub track_index: usize,
+    pub segment_index: usize,
+    pub point_index: usize,
+    pub coord: Coordinate,
+}
+
+impl Cue {
+    /// Human-readable cue, e.g. `Turn left at km 3.2`.
+    pub fn description(&self) -> String {
+        format!(
+            "{} at km {:.1}",
+            self.turn.instruction(),
+            self.distance / 1000.0
+        )
+    }
+}
+
+/// Finds turns along every track segment from changes in bearing.
+pub fn detect(route: &Route, options: &CueOptions) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut offset = 0.0;
+
+    for (track_index, track) in route.tracks.iter().enumerate() {
+        for (segment_index, segment) in track.segments.iter().enumerate() {
+            let points = &segment.points;
+            let distances = cumulative(points);
+            let mut cluster: Option<Cue> = None;
+
+            for i in 1..points.len().saturating_sub(1) {
+                let Some(angle) = turn_angle(points, &distances, i, options.window) else {
+                    continue;
+                };
+                if angle.abs() < options.min_angle {
+                    continue;
+                }
+
+                let cue = Cue {
+                    turn: classify(angle, options),
+                    angle,
+                    distance: offset + distances[i],
+                    track_index,
+                    segment_index,
+                    point_index: i,
+                    coord: points[i].clone(),
+                };
+
+                match cluster.take() {
+                    Some(current) if cue.distance - current.distance < options.min_spacing => {
+                        cluster = Some(if cue.angle.abs() > current.angle.abs() {
+                            cue
+                        } else {
+                            current
+                        });
+                    }
+                    Some(current) => {
+                        cues.push(current);
+                        cluster = Some(cue);
+                    }
+                    None => cluster = Some(cue),
+                }
+            }
+
+            cues.extend(cluster);
+            offset += length(points);
+        }
+    }
+
+    cues
+}
+
+/// Signed change of direction at `points[index]`, comparing the bearing over
+/// the `window` metres before it with the bearing over the `window` after.
+fn turn_angle(points: &[Coordinate], distances: &[f64], index: usize, window: f64) -> Option<f64> {
+    let here = &points[index];
+    let before = point_at(points, distances, distances[index] - window);
+    let after = point_at(points, distances, distances[index] + window);
+    if haversine(&before, here) < 1.0 || haversine(here, &after) < 1.0 {
+        return None;
+    }
+
+    let change = bearing(here, &after) - bearing(&before, here);
+    Some((change + 540.0).rem_euclid(360.0) - 180.0)
+}
+
+fn classify(angle: f64, options: &CueOptions) -> Turn {
+    let magnitude = angle.abs();
+    if magnitude >= options.u_turn_angle {
+        Turn::UTurn
+    } else if magnitude >= options.sharp_angle {
+        if angle > 0.0 {
+            Turn::SharpRight
+        } else {
+            Turn::SharpLeft
+        }
+    } else if angle > 0.0 {
+        Turn::Right
+    } else {
+        Turn::Left
+    }
+}
+
+/// Cues as waypoints carrying the turn in `<sym>` and `turn` in `<type>`.
+pub fn to_waypoints(cues: &[Cue]) -> Vec<Waypoint> {
+    cues.iter()
+        .map(|cue| Waypoint {
+            coord: cue.coord.clone(),
+            name: Some(cue.description()),
+            symbol: Some(cue.turn.symbol().to_string()),
+            kind: Some("turn".to_string()),
+        })
+        .collect()
+}
+
+pub fn to_csv(cues: &[Cue]) -> String {
+    let mut csv = String::from("distance_km,turn,instruction,lat,lon\n");
+    for cue in cues {
+        csv.push_str(&format!(
+            "{:.2},{},{},{:.6},{:.6}\n",
+            cue.distance / 1000.0,
+            cue.turn.as_str(),
+            cue.turn.instruction(),
+            cue.coord.lat,
+            cue.coord.lon
+        ));
+    }
+    csv
+}
+
+/// A standalone, printable HTML cue sheet.
+pub fn to_html(cues: &[Cue], title: &str) -> String {
+    let title = escape_html(title);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+         <style>table{{border-collapse:collapse}}td,th{{border:1px solid #000;padding:4px 8px;text-align:left}}</style>\n\
+         </head>\n<body>\n<h1>{0}</h1>\n<table>\n<tr><th>km</th><th>Direction</th></tr>\n",
+        title
+    );
+    for cue in cues {
+        html.push_str(&format!(
+            "<tr><td>{:.1}</td><td>{}</td></tr>\n",
+            cue.distance / 1000.0,
+            cue.turn.instruction()
+        ));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Track, TrackSegment};
+
+    /// Heads east for ~1.1 km, then follows each listed bearing change,
+    /// with points every ~11 m.
+    fn route(turns: &[f64]) -> Route {
+        let step = 0.0001;
+        let mut points = vec![Coordinate::new(0.0, 0.0)];
+        let mut heading: f64 = 90.0;
+        for leg in 0..=turns.len() {
+            for _ in 0..100 {
+                let last = points.last().unwrap();
+                points.push(Coordinate::new(
+                    last.lat + step * heading.to_radians().cos(),
+                    last.lon + step * heading.to_radians().sin(),
+                ));
+            }
+            if let Some(turn) = turns.get(leg) {
+                heading += turn;
+            }
+        }
+
+        let mut route = Route::new();
+        route.add_track(Track::new(vec![TrackSegment::new(points)]));
+        route
+    }
+
+    #[test]
+    fn test_detect_left_and_right() {
+        let cues = detect(&route(&[-90.0, 90.0]), &CueOptions::default());
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].turn, Turn::Left);
+        assert_eq!(cues[1].turn, Turn::Right);
+        assert_eq!(cues[0].point_index, 100);
+        assert!((cues[0].angle + 90.0).abs() < 1.0);
+        assert!((cues[1].distance - 2.0 * cues[0].distance).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_classify_sharp_and_u_turn() {
+        let cues = detect(&route(&[135.0, -178.0]), &CueOptions::default());
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].turn, Turn::SharpRight);
+        assert_eq!(cues[1].turn, Turn::UTurn);
+    }
+
+    #[test]
+    fn test_ignores_gentle_bends() {
+        let cues = detect(&route(&[15.0, -20.0]), &CueOptions::default());
+        assert!(cues.is_empty());
+    }
+
+    #[test]
+    fn test_merges_nearby_turns() {
+        let options = CueOptions {
+            min_spacing: 2000.0,
+            ..Default::default()
+        };
+        let cues = detect(&route(&[-60.0, 120.0]), &options);
+        assert_eq!(cues.len(), 1);
+        assert_eq!(cues[0].turn, Turn::SharpRight);
+    }
+
+    #[test]
+    fn test_cue_waypoints() {
+        let cues = detect(&route(&[-90.0]), &CueOptions::default());
+        let waypoints = to_waypoints(&cues);
+        assert_eq!(waypoints[0].name.as_deref(), Some("Turn left at km 1.1"));
+        assert_eq!(waypoints[0].symbol.as_deref(), Some("Left"));
+        assert_eq!(waypoints[0].kind.as_deref(), Some("turn"));
+    }
+
+    #[test]
+    fn test_cue_sheets() {
+        let cues = detect(&route(&[90.0]), &CueOptions::default());
+        let csv = to_csv(&cues);
+        assert!(csv.starts_with("distance_km,turn,instruction,lat,lon\n"));
+        assert!(csv.contains("1.11,right,Turn right,"));
+
+        let html = to_html(&cues, "Tom & Jerry's <ride>");
+        assert!(html.contains("<title>Tom &amp; Jerry's &lt;ride&gt;</title>"));
+        assert!(html.contains("<td>1.1</td><td>Turn right</td>"));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod climbs;
+pub mod compare;
+pub mod cues;
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable machine-readable identifier, e.g. `missing_elevation_tile`.
+    pub code: String,
+    pub message: String,
+}
+
+/// Non-fatal findings collected while processing a route.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Diagnostics {
+    pub entries: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn info(&mut self, code: &str, message: String) {
+        self.push(Severity::Info, code, message);
+    }
+
+    pub fn warning(&mut self, code: &str, message: String) {
+        self.push(Severity::Warning, code, message);
+    }
+
+    pub fn extend(&mut self, other: Diagnostics) {
+        self.entries.extend(other.entries);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.entries
+            .iter()
+            .filter(|d| d.severity == Severity::Warning)
+    }
+
+    fn push(&mut self, severity: Severity, code: &str, message: String) {
+        self.entries.push(Diagnostic {
+            severity,
+            code: code.to_string(),
+            message,
+        });
+    }
+}
@@ -0,0 +1,432 @@
+use super::grid::Grid;
+use super::{ElevationError, ElevationProvider};
+use std::path::Path;
+
+const TAG_IMAGE_WIDTH: u16 = 256;
+const TAG_IMAGE_LENGTH: u16 = 257;
+const TAG_BITS_PER_SAMPLE: u16 = 258;
+const TAG_COMPRESSION: u16 = 259;
+const TAG_STRIP_OFFSETS: u16 = 273;
+const TAG_SAMPLES_PER_PIXEL: u16 = 277;
+const TAG_ROWS_PER_STRIP: u16 = 278;
+const TAG_TILE_WIDTH: u16 = 322;
+const TAG_TILE_LENGTH: u16 = 323;
+const TAG_TILE_OFFSETS: u16 = 324;
+const TAG_SAMPLE_FORMAT: u16 = 339;
+const TAG_MODEL_PIXEL_SCALE: u16 = 33550;
+const TAG_MODEL_TIEPOINT: u16 = 33922;
+const TAG_GEO_KEY_DIRECTORY: u16 = 34735;
+const TAG_GDAL_NODATA: u16 = 42113;
+
+const KEY_RASTER_TYPE: u16 = 1025;
+const RASTER_PIXEL_IS_POINT: u16 = 2;
+
+const FORMAT_UINT: u16 = 1;
+const FORMAT_INT: u16 = 2;
+const FORMAT_FLOAT: u16 = 3;
+
+/// Elevation from single-band, uncompressed GeoTIFF DEMs in WGS-84
+/// geographic coordinates, such as SRTM or Copernicus GLO-30 exports.
+#[derive(Default)]
+pub struct GeoTiffProvider {
+    grids: Vec<Grid>,
+}
+
+impl GeoTiffProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every `.tif` and `.tiff` file in a directory.
+    pub fn from_directory(directory: impl AsRef<Path>) -> Result<Self, ElevationError> {
+        let mut provider = Self::new();
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            let is_tiff = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("tif") || e.eq_ignore_ascii_case("tiff"));
+            if is_tiff {
+                provider.add_tiff(&std::fs::read(&path)?)?;
+            }
+        }
+        Ok(provider)
+    }
+
+    pub fn add_tiff(&mut self, bytes: &[u8]) -> Result<(), ElevationError> {
+        self.grids.push(decode(bytes)?);
+        Ok(())
+    }
+}
+
+impl ElevationProvider for GeoTiffProvider {
+    fn elevation(&self, lat: f64, lon: f64) -> Result<Option<f64>, ElevationError> {
+        self.grids
+            .iter()
+            .find(|grid| grid.contains(lat, lon))
+            .map(|grid| grid.sample(lat, lon))
+            .ok_or(ElevationError::NoCoverage)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl Reader<'_> {
+    fn bytes(&self, offset: usize, len: usize) -> Result<&[u8], ElevationError> {
+        offset
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(offset..end))
+            .ok_or_else(|| invalid("unexpected end of file"))
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, ElevationError> {
+        let b: [u8; 2] = self.bytes(offset, 2)?.try_into().unwrap();
+        Ok(if self.little_endian {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, ElevationError> {
+        let b: [u8; 4] = self.bytes(offset, 4)?.try_into().unwrap();
+        Ok(if self.little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64, ElevationError> {
+        let b: [u8; 8] = self.bytes(offset, 8)?.try_into().unwrap();
+        Ok(if self.little_endian {
+            u64::from_le_bytes(b)
+        } else {
+            u64::from_be_bytes(b)
+        })
+    }
+}
+
+struct Entry {
+    tag: u16,
+    field_type: u16,
+    count: usize,
+    /// Offset of the value itself, which is inline when it fits in four bytes.
+    offset: usize,
+}
+
+impl Entry {
+    fn numbers(&self, reader: &Reader) -> Result<Vec<f64>, ElevationError> {
+        (0..self.count)
+            .map(|i| match self.field_type {
+                1 => Ok(reader.bytes(self.offset + i, 1)?[0] as f64),
+                3 => Ok(reader.u16(self.offset + i * 2)? as f64),
+                4 => Ok(reader.u32(self.offset + i * 4)? as f64),
+                12 => Ok(f64::from_bits(reader.u64(self.offset + i * 8)?)),
+                other => Err(invalid(&format!(
+                    "unsupported type {} for tag {}",
+                    other, self.tag
+                ))),
+            })
+            .collect()
+    }
+
+    fn text(&self, reader: &Reader) -> Result<String, ElevationError> {
+        let bytes = reader.bytes(self.offset, self.count)?;
+        Ok(String::from_utf8_lossy(bytes)
+            .trim_end_matches('\0')
+            .trim()
+            .to_string())
+    }
+}
+
+fn type_size(field_type: u16) -> usize {
+    match field_type {
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => 1,
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<Grid, ElevationError> {
+    let little_endian = match bytes.get(0..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return Err(invalid("not a TIFF file")),
+    };
+    let reader = Reader {
+        bytes,
+        little_endian,
+    };
+    if reader.u16(2)? != 42 {
+        return Err(invalid("only classic TIFF is supported"));
+    }
+
+    let ifd = reader.u32(4)? as usize;
+    let entries: Vec<Entry> = (0..reader.u16(ifd)? as usize)
+        .map(|i| {
+            let at = ifd + 2 + i * 12;
+            let field_type = reader.u16(at + 2)?;
+            let count = reader.u32(at + 4)? as usize;
+            let inline = type_size(field_type)
+                .checked_mul(count)
+                .is_some_and(|size| size <= 4);
+            let offset = if inline {
+                at + 8
+            } else {
+                reader.u32(at + 8)? as usize
+            };
+            Ok(Entry {
+                tag: reader.u16(at)?,
+                field_type,
+                count,
+                offset,
+            })
+        })
+        .collect::<Result<_, ElevationError>>()?;
+
+    let find = |tag| entries.iter().find(|e| e.tag == tag);
+    let numbers = |tag| -> Result<Option<Vec<f64>>, ElevationError> {
+        find(tag).map(|e| e.numbers(&reader)).transpose()
+    };
+    let number = |tag| -> Result<Option<usize>, ElevationError> {
+        Ok(numbers(tag)?.and_then(|v| v.first().map(|&n| n as usize)))
+    };
+    let required = |tag| number(tag)?.ok_or_else(|| invalid(&format!("missing tag {}", tag)));
+
+    let width = required(TAG_IMAGE_WIDTH)?;
+    let height = required(TAG_IMAGE_LENGTH)?;
+    if width < 2 || height < 2 {
+        return Err(invalid("raster must be at least 2x2"));
+    }
+    if number(TAG_COMPRESSION)?.unwrap_or(1) != 1 {
+        return Err(invalid("compressed rasters are not supported"));
+    }
+    if number(TAG_SAMPLES_PER_PIXEL)?.unwrap_or(1) != 1 {
+        return Err(invalid("only single-band rasters are supported"));
+    }
+
+    let bits = number(TAG_BITS_PER_SAMPLE)?.unwrap_or(1);
+    let format = number(TAG_SAMPLE_FORMAT)?.unwrap_or(FORMAT_UINT as usize) as u16;
+    let sample_size = bits / 8;
+    let read_sample = |offset: usize| -> Result<f32, ElevationError> {
+        Ok(match (format, bits) {
+            (FORMAT_INT, 16) => reader.u16(offset)? as i16 as f32,
+            (FORMAT_UINT, 16) => reader.u16(offset)? as f32,
+            (FORMAT_INT, 32) => reader.u32(offset)? as i32 as f32,
+            (FORMAT_FLOAT, 32) => f32::from_bits(reader.u32(offset)?),
+            (FORMAT_FLOAT, 64) => f64::from_bits(reader.u64(offset)?) as f32,
+            _ => {
+                return Err(invalid(&format!(
+                    "unsupported sample format {} with {} bits",
+                    format, bits
+                )))
+            }
+        })
+    };
+
+    // Strips are tiles that span the full width.
+    let (block_width, block_height, offsets) = match numbers(TAG_TILE_OFFSETS)? {
+        Some(offsets) => (
+            required(TAG_TILE_WIDTH)?,
+            required(TAG_TILE_LENGTH)?,
+            offsets,
+        ),
+        None => (
+            width,
+            number(TAG_ROWS_PER_STRIP)?.unwrap_or(height).min(height),
+            numbers(TAG_STRIP_OFFSETS)?.ok_or_else(|| invalid("missing strip offsets"))?,
+        ),
+    };
+    if block_width == 0 || block_height == 0 {
+        return Err(invalid("zero block size"));
+    }
+    let blocks_across = width.div_ceil(block_width);
+
+    // Check the header's dimensions against the file before trusting them
+    // with an allocation.
+    let raster_size = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(sample_size.max(1)));
+    if raster_size.is_none_or(|size| size > bytes.len()) {
+        return Err(invalid("raster is larger than the file"));
+    }
+
+    let nodata = find(TAG_GDAL_NODATA)
+        .map(|e| e.text(&reader))
+        .transpose()?
+        .and_then(|s| s.parse::<f32>().ok());
+
+    let mut data = Vec::with_capacity(width * height);
+    for row in 0..height {
+        for col in 0..width {
+            let block = (row / block_height) * blocks_across + col / block_width;
+            let start = *offsets
+                .get(block)
+                .ok_or_else(|| invalid("missing raster block"))? as usize;
+            let offset = (row % block_height)
+                .checked_mul(block_width)
+                .and_then(|n| n.checked_add(col % block_width))
+                .and_then(|n| n.checked_mul(sample_size))
+                .and_then(|n| n.checked_add(start))
+                .ok_or_else(|| invalid("raster block out of range"))?;
+            let value = read_sample(offset)?;
+            data.push(if Some(value) == nodata {
+                f32::NAN
+            } else {
+                value
+            });
+        }
+    }
+
+    let scale = numbers(TAG_MODEL_PIXEL_SCALE)?
+        .filter(|s| s.len() >= 2)
+        .ok_or_else(|| invalid("missing ModelPixelScale"))?;
+    let tiepoint = numbers(TAG_MODEL_TIEPOINT)?
+        .filter(|t| t.len() >= 6)
+        .ok_or_else(|| invalid("missing ModelTiepoint"))?;
+
+    // By default the tiepoint refers to the corner of a pixel rather than its
+    // centre, where the sample actually lies.
+    let pixel_is_point = numbers(TAG_GEO_KEY_DIRECTORY)?.is_some_and(|keys| {
+        keys.chunks_exact(4).skip(1).any(|key| {
+            key[0] as u16 == KEY_RASTER_TYPE
+                && key[1] == 0.0
+                && key[3] as u16 == RASTER_PIXEL_IS_POINT
+        })
+    });
+    let centre = if pixel_is_point { 0.0 } else { 0.5 };
+
+    Ok(Grid {
+        north: tiepoint[4] + (tiepoint[1] - centre) * scale[1],
+        west: tiepoint[3] - (tiepoint[0] - centre) * scale[0],
+        lat_step: scale[1],
+        lon_step: scale[0],
+        width,
+        height,
+        data,
+    })
+}
+
+fn invalid(message: &str) -> ElevationError {
+    ElevationError::InvalidTile(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a little-endian, single-strip int16 GeoTIFF with pixel-is-point
+    /// georeferencing.
+    fn geotiff(
+        width: u16,
+        height: u16,
+        values: &[i16],
+        north: f64,
+        west: f64,
+        step: f64,
+    ) -> Vec<u8> {
+        let mut out = b"II".to_vec();
+        out.extend(42u16.to_le_bytes());
+        out.extend(8u32.to_le_bytes());
+
+        let entries = 12u32;
+        let extra = 8 + 2 + entries * 12 + 4;
+        let scale_at = extra;
+        let tie_at = scale_at + 24;
+        let keys_at = tie_at + 48;
+        let nodata_at = keys_at + 16;
+        let data_at = nodata_at + 8;
+
+        out.extend((entries as u16).to_le_bytes());
+        let mut entry = |tag: u16, field_type: u16, count: u32, value: u32| {
+            out.extend(tag.to_le_bytes());
+            out.extend(field_type.to_le_bytes());
+            out.extend(count.to_le_bytes());
+            out.extend(value.to_le_bytes());
+        };
+        entry(TAG_IMAGE_WIDTH, 3, 1, width as u32);
+        entry(TAG_IMAGE_LENGTH, 3, 1, height as u32);
+        entry(TAG_BITS_PER_SAMPLE, 3, 1, 16);
+        entry(TAG_COMPRESSION, 3, 1, 1);
+        entry(TAG_STRIP_OFFSETS, 4, 1, data_at);
+        entry(TAG_SAMPLES_PER_PIXEL, 3, 1, 1);
+        entry(TAG_ROWS_PER_STRIP, 3, 1, height as u32);
+        entry(TAG_SAMPLE_FORMAT, 3, 1, FORMAT_INT as u32);
+        entry(TAG_MODEL_PIXEL_SCALE, 12, 3, scale_at);
+        entry(TAG_MODEL_TIEPOINT, 12, 6, tie_at);
+        entry(TAG_GEO_KEY_DIRECTORY, 3, 8, keys_at);
+        entry(TAG_GDAL_NODATA, 2, 7, nodata_at);
+        out.extend(0u32.to_le_bytes());
+
+        for v in [step, step, 0.0, 0.0, 0.0, 0.0, west, north, 0.0] {
+            out.extend(v.to_le_bytes());
+        }
+        for v in [1u16, 1, 0, 1, KEY_RASTER_TYPE, 0, 1, RASTER_PIXEL_IS_POINT] {
+            out.extend(v.to_le_bytes());
+        }
+        out.extend(b"-32768\0\0");
+        for v in values {
+            out.extend(v.to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn test_decode_strip_raster() {
+        let bytes = geotiff(3, 2, &[0, 10, 20, 100, 110, 120], 46.0, 7.0, 0.5);
+        let mut provider = GeoTiffProvider::new();
+        provider.add_tiff(&bytes).unwrap();
+
+        assert_eq!(provider.elevation(46.0, 7.0).unwrap(), Some(0.0));
+        assert_eq!(provider.elevation(45.5, 8.0).unwrap(), Some(120.0));
+        assert_eq!(provider.elevation(45.75, 7.25).unwrap(), Some(55.0));
+    }
+
+    #[test]
+    fn test_nodata_is_void() {
+        let bytes = geotiff(2, 2, &[-32768, -32768, -32768, 40], 1.0, 0.0, 1.0);
+        let mut provider = GeoTiffProvider::new();
+        provider.add_tiff(&bytes).unwrap();
+
+        assert_eq!(provider.elevation(0.5, 0.5).unwrap(), Some(40.0));
+        assert_eq!(provider.elevation(1.0, 0.0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_outside_coverage() {
+        let bytes = geotiff(2, 2, &[0, 0, 0, 0], 1.0, 0.0, 1.0);
+        let mut provider = GeoTiffProvider::new();
+        provider.add_tiff(&bytes).unwrap();
+        assert!(matches!(
+            provider.elevation(5.0, 5.0),
+            Err(ElevationError::NoCoverage)
+        ));
+    }
+
+    #[test]
+    fn test_rejects_dimensions_larger_than_file() {
+        let mut bytes = geotiff(2, 2, &[0, 0, 0, 0], 1.0, 0.0, 1.0);
+        // Rewrite width and height as 100000 LONGs.
+        for entry in [10, 22] {
+            bytes[entry + 2..entry + 4].copy_from_slice(&4u16.to_le_bytes());
+            bytes[entry + 8..entry + 12].copy_from_slice(&100_000u32.to_le_bytes());
+        }
+
+        let mut provider = GeoTiffProvider::new();
+        assert!(matches!(
+            provider.add_tiff(&bytes),
+            Err(ElevationError::InvalidTile(message)) if message.contains("larger than the file")
+        ));
+    }
+
+    #[test]
+    fn test_rejects_non_tiff() {
+        let mut provider = GeoTiffProvider::new();
+        assert!(provider.add_tiff(b"not a tiff").is_err());
+    }
+}
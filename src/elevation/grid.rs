@@ -0,0 +1,94 @@
+/// A regular lat/lon grid of elevation samples, stored row by row from the
+/// north edge. Voids are stored as NaN.
+pub struct Grid {
+    /// Latitude and longitude of the first (north-west) sample.
+    pub north: f64,
+    pub west: f64,
+    /// Spacing between samples in degrees.
+    pub lat_step: f64,
+    pub lon_step: f64,
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<f32>,
+}
+
+impl Grid {
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        let south = self.north - self.lat_step * (self.height - 1) as f64;
+        let east = self.west + self.lon_step * (self.width - 1) as f64;
+        (south..=self.north).contains(&lat) && (self.west..=east).contains(&lon)
+    }
+
+    /// Bilinear interpolation between the four surrounding samples. Void
+    /// samples are left out and the remaining weights renormalised.
+    pub fn sample(&self, lat: f64, lon: f64) -> Option<f64> {
+        let row = ((self.north - lat) / self.lat_step).clamp(0.0, (self.height - 1) as f64);
+        let col = ((lon - self.west) / self.lon_step).clamp(0.0, (self.width - 1) as f64);
+
+        let r0 = (row.floor() as usize).min(self.height.saturating_sub(2));
+        let c0 = (col.floor() as usize).min(self.width.saturating_sub(2));
+        let r1 = (r0 + 1).min(self.height - 1);
+        let c1 = (c0 + 1).min(self.width - 1);
+        let dr = row - r0 as f64;
+        let dc = col - c0 as f64;
+
+        let mut total = 0.0;
+        let mut weight = 0.0;
+        for (r, c, w) in [
+            (r0, c0, (1.0 - dr) * (1.0 - dc)),
+            (r0, c1, (1.0 - dr) * dc),
+            (r1, c0, dr * (1.0 - dc)),
+            (r1, c1, dr * dc),
+        ] {
+            let value = self.data[r * self.width + c];
+            if !value.is_nan() && w > 0.0 {
+                total += value as f64 * w;
+                weight += w;
+            }
+        }
+
+        (weight > 0.0).then(|| total / weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Grid {
+        Grid {
+            north: 1.0,
+            west: 0.0,
+            lat_step: 1.0,
+            lon_step: 1.0,
+            width: 2,
+            height: 2,
+            data: vec![0.0, 10.0, 20.0, 30.0],
+        }
+    }
+
+    #[test]
+    fn test_bilinear_sample() {
+        let grid = grid();
+        assert_eq!(grid.sample(1.0, 0.0), Some(0.0));
+        assert_eq!(grid.sample(0.0, 1.0), Some(30.0));
+        assert_eq!(grid.sample(0.5, 0.5), Some(15.0));
+        assert_eq!(grid.sample(1.0, 0.25), Some(2.5));
+    }
+
+    #[test]
+    fn test_sample_skips_voids() {
+        let mut grid = grid();
+        grid.data[3] = f32::NAN;
+        assert_eq!(grid.sample(0.5, 0.5), Some(10.0));
+        grid.data = vec![f32::NAN; 4];
+        assert_eq!(grid.sample(0.5, 0.5), None);
+    }
+
+    #[test]
+    fn test_contains() {
+        let grid = grid();
+        assert!(grid.contains(0.5, 0.5));
+        assert!(!grid.contains(1.5, 0.5));
+    }
+}
@@ -0,0 +1,253 @@
+use super::grid::Grid;
+use super::{ElevationError, ElevationProvider};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const VOID: i16 = -32768;
+
+/// Elevation from SRTM `.hgt` tiles, either loaded up front from memory or
+/// read lazily from a directory. Both 1" (3601²) and 3" (1201²) tiles work.
+pub struct HgtProvider {
+    directory: Option<PathBuf>,
+    tiles: RefCell<HashMap<(i32, i32), Tile>>,
+}
+
+/// A tile slot, cached whether or not the tile could be used so that each
+/// file is read at most once.
+enum Tile {
+    Loaded(Grid),
+    Missing,
+    Invalid(String),
+}
+
+impl HgtProvider {
+    pub fn new() -> Self {
+        Self {
+            directory: None,
+            tiles: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_directory(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: Some(directory.into()),
+            tiles: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Adds a tile from its raw bytes. `name` is the tile's file name, such
+    /// as `N37W123.hgt`, which gives its south-west corner.
+    pub fn add_tile(&mut self, name: &str, bytes: &[u8]) -> Result<(), ElevationError> {
+        let (lat, lon) = parse_tile_name(name)
+            .ok_or_else(|| ElevationError::InvalidTile(format!("bad tile name {}", name)))?;
+        let grid = decode(lat, lon, bytes)?;
+        self.tiles
+            .borrow_mut()
+            .insert((lat, lon), Tile::Loaded(grid));
+        Ok(())
+    }
+
+    fn load(&self, lat: i32, lon: i32) -> Result<(), ElevationError> {
+        if self.tiles.borrow().contains_key(&(lat, lon)) {
+            return Ok(());
+        }
+
+        let tile = match &self.directory {
+            Some(directory) => {
+                let path = directory.join(format!("{}.hgt", tile_name(lat, lon)));
+                match std::fs::read(&path) {
+                    Ok(bytes) => match decode(lat, lon, &bytes) {
+                        Ok(grid) => Tile::Loaded(grid),
+                        Err(ElevationError::InvalidTile(message)) => Tile::Invalid(message),
+                        Err(e) => return Err(e),
+                    },
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Tile::Missing,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            None => Tile::Missing,
+        };
+
+        self.tiles.borrow_mut().insert((lat, lon), tile);
+        Ok(())
+    }
+}
+
+impl Default for HgtProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ElevationProvider for HgtProvider {
+    fn elevation(&self, lat: f64, lon: f64) -> Result<Option<f64>, ElevationError> {
+        let (tile_lat, tile_lon) = (lat.floor() as i32, lon.floor() as i32);
+        self.load(tile_lat, tile_lon)?;
+
+        match self.tiles.borrow().get(&(tile_lat, tile_lon)) {
+            Some(Tile::Loaded(grid)) => Ok(grid.sample(lat, lon)),
+            Some(Tile::Invalid(message)) => Err(ElevationError::InvalidTile(message.clone())),
+            _ => Err(ElevationError::MissingTile(tile_name(tile_lat, tile_lon))),
+        }
+    }
+}
+
+pub fn tile_name(lat: i32, lon: i32) -> String {
+    format!(
+        "{}{:02}{}{:03}",
+        if lat < 0 { 'S' } else { 'N' },
+        lat.abs(),
+        if lon < 0 { 'W' } else { 'E' },
+        lon.abs()
+    )
+}
+
+fn parse_tile_name(name: &str) -> Option<(i32, i32)> {
+    let file = name.rsplit(['/', '\\']).next()?;
+    let stem = file.split('.').next()?.to_ascii_uppercase();
+    if stem.len() != 7 {
+        return None;
+    }
+
+    let lat: i32 = stem.get(1..3)?.parse().ok()?;
+    let lon: i32 = stem.get(4..7)?.parse().ok()?;
+    let lat = match stem.as_bytes()[0] {
+        b'N' => lat,
+        b'S' => -lat,
+        _ => return None,
+    };
+    let lon = match stem.as_bytes()[3] {
+        b'E' => lon,
+        b'W' => -lon,
+        _ => return None,
+    };
+    Some((lat, lon))
+}
+
+fn decode(lat: i32, lon: i32, bytes: &[u8]) -> Result<Grid, ElevationError> {
+    let samples = bytes.len() / 2;
+    let size = (samples as f64).sqrt() as usize;
+    if size < 2 || size * size * 2 != bytes.len() {
+        return Err(ElevationError::InvalidTile(format!(
+            "{} has {} bytes, which is not a square grid",
+            tile_name(lat, lon),
+            bytes.len()
+        )));
+    }
+
+    let data = bytes
+        .chunks_exact(2)
+        .map(|b| match i16::from_be_bytes([b[0], b[1]]) {
+            VOID => f32::NAN,
+            value => value as f32,
+        })
+        .collect();
+
+    let step = 1.0 / (size - 1) as f64;
+    Ok(Grid {
+        north: lat as f64 + 1.0,
+        west: lon as f64,
+        lat_step: step,
+        lon_step: step,
+        width: size,
+        height: size,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 3x3 tile whose value rises by 10 m per sample eastwards and 100 m
+    // per sample southwards.
+    fn tile() -> Vec<u8> {
+        (0..9)
+            .flat_map(|i: i16| ((i / 3) * 100 + (i % 3) * 10).to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_tile_names() {
+        assert_eq!(tile_name(37, -123), "N37W123");
+        assert_eq!(tile_name(-1, 5), "S01E005");
+        assert_eq!(parse_tile_name("srtm/N37W123.hgt"), Some((37, -123)));
+        assert_eq!(parse_tile_name("s01e005.HGT"), Some((-1, 5)));
+        assert_eq!(parse_tile_name("elevation.hgt"), None);
+    }
+
+    #[test]
+    fn test_in_memory_tile() {
+        let mut provider = HgtProvider::new();
+        provider.add_tile("N10E020.hgt", &tile()).unwrap();
+
+        assert_eq!(provider.elevation(10.5, 20.0).unwrap(), Some(100.0));
+        assert_eq!(provider.elevation(10.0, 20.5).unwrap(), Some(210.0));
+        assert_eq!(provider.elevation(10.75, 20.25).unwrap(), Some(55.0));
+    }
+
+    #[test]
+    fn test_void_samples() {
+        let mut bytes = tile();
+        for i in 0..9 {
+            bytes[i * 2..i * 2 + 2].copy_from_slice(&VOID.to_be_bytes());
+        }
+        let mut provider = HgtProvider::new();
+        provider.add_tile("N10E020", &bytes).unwrap();
+        assert_eq!(provider.elevation(10.5, 20.5).unwrap(), None);
+    }
+
+    #[test]
+    fn test_missing_tile() {
+        let provider = HgtProvider::new();
+        assert!(matches!(
+            provider.elevation(37.5, -122.5),
+            Err(ElevationError::MissingTile(name)) if name == "N37W123"
+        ));
+    }
+
+    #[test]
+    fn test_invalid_tile_size() {
+        let mut provider = HgtProvider::new();
+        assert!(provider.add_tile("N10E020", &[0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_directory_provider() {
+        let directory = std::env::temp_dir().join(format!("hgt-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("N10E020.hgt"), tile()).unwrap();
+
+        let provider = HgtProvider::from_directory(&directory);
+        assert_eq!(provider.elevation(10.5, 20.5).unwrap(), Some(110.0));
+        assert!(matches!(
+            provider.elevation(12.5, 20.5),
+            Err(ElevationError::MissingTile(_))
+        ));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_tile_is_read_once() {
+        let directory =
+            std::env::temp_dir().join(format!("hgt-corrupt-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("N10E020.hgt");
+        std::fs::write(&path, [0, 0, 0]).unwrap();
+
+        let provider = HgtProvider::from_directory(&directory);
+        assert!(matches!(
+            provider.elevation(10.5, 20.5),
+            Err(ElevationError::InvalidTile(_))
+        ));
+
+        // The failure is cached, so the file is not needed any more.
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(matches!(
+            provider.elevation(10.6, 20.6),
+            Err(ElevationError::InvalidTile(_))
+        ));
+    }
+}
@@ -0,0 +1,134 @@
+mod geotiff;
+mod grid;
+mod hgt;
+pub mod smooth;
+
+pub use geotiff::GeoTiffProvider;
+pub use hgt::HgtProvider;
+
+use crate::diagnostics::Diagnostics;
+use crate::types::Route;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ElevationError {
+    #[error("Missing elevation tile {0}")]
+    MissingTile(String),
+    #[error("No elevation data covers this area")]
+    NoCoverage,
+    #[error("Invalid elevation tile: {0}")]
+    InvalidTile(String),
+    #[error("IO error: {0}")]
+    IoError(String),
+}
+
+impl From<std::io::Error> for ElevationError {
+    fn from(e: std::io::Error) -> Self {
+        ElevationError::IoError(e.to_string())
+    }
+}
+
+pub trait ElevationProvider {
+    /// Elevation in metres at a position, or `Ok(None)` if the data has a
+    /// void there.
+    fn elevation(&self, lat: f64, lon: f64) -> Result<Option<f64>, ElevationError>;
+}
+
+/// Fills `ele` on every waypoint and track point from `provider`. Points that
+/// already have an elevation are kept unless `overwrite` is set. Lookup
+/// failures such as missing tiles are reported as warnings and leave the
+/// point unchanged.
+pub fn enrich(route: &mut Route, provider: &dyn ElevationProvider, overwrite: bool) -> Diagnostics {
+    let mut failures: BTreeMap<String, usize> = BTreeMap::new();
+    let mut voids = 0;
+
+    let coords = route.waypoints.iter_mut().map(|w| &mut w.coord).chain(
+        route
+            .tracks
+            .iter_mut()
+            .flat_map(|t| t.segments.iter_mut())
+            .flat_map(|s| s.points.iter_mut()),
+    );
+
+    for coord in coords {
+        if coord.ele.is_some() && !overwrite {
+            continue;
+        }
+        match provider.elevation(coord.lat, coord.lon) {
+            Ok(Some(ele)) => coord.ele = Some(ele),
+            Ok(None) => voids += 1,
+            Err(e) => *failures.entry(e.to_string()).or_default() += 1,
+        }
+    }
+
+    let mut diagnostics = Diagnostics::new();
+    for (message, count) in failures {
+        diagnostics.warning(
+            "elevation_lookup_failed",
+            format!("{} ({} points left without elevation)", message, count),
+        );
+    }
+    if voids > 0 {
+        diagnostics.warning(
+            "elevation_void",
+            format!("{} points fall in elevation data voids", voids),
+        );
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Coordinate, Track, TrackSegment, Waypoint};
+
+    struct Slope;
+
+    impl ElevationProvider for Slope {
+        fn elevation(&self, lat: f64, lon: f64) -> Result<Option<f64>, ElevationError> {
+            if lat < 0.0 {
+                return Err(ElevationError::MissingTile("S01E000".to_string()));
+            }
+            Ok(Some(lon * 1000.0))
+        }
+    }
+
+    #[test]
+    fn test_enrich_fills_missing_elevation() {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::new(Coordinate::new(0.5, 0.1)));
+        route.add_track(Track::new(vec![TrackSegment::new(vec![
+            Coordinate::new(0.5, 0.2),
+            Coordinate::with_elevation(0.5, 0.3, 5.0),
+        ])]));
+
+        let diagnostics = enrich(&mut route, &Slope, false);
+        assert!(diagnostics.is_empty());
+        assert_eq!(route.waypoints[0].coord.ele, Some(100.0));
+        assert_eq!(route.tracks[0].segments[0].points[0].ele, Some(200.0));
+        assert_eq!(route.tracks[0].segments[0].points[1].ele, Some(5.0));
+    }
+
+    #[test]
+    fn test_enrich_overwrite() {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::new(Coordinate::with_elevation(0.5, 0.3, 5.0)));
+        enrich(&mut route, &Slope, true);
+        assert_eq!(route.waypoints[0].coord.ele, Some(300.0));
+    }
+
+    #[test]
+    fn test_enrich_missing_tile_is_warning() {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::new(Coordinate::new(-0.5, 0.1)));
+        route.add_waypoint(Waypoint::new(Coordinate::new(-0.6, 0.1)));
+
+        let diagnostics = enrich(&mut route, &Slope, false);
+        let warnings: Vec<_> = diagnostics.warnings().collect();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("S01E000"));
+        assert!(warnings[0].message.contains("2 points"));
+        assert!(route.waypoints[0].coord.ele.is_none());
+    }
+}
@@ -0,0 +1,301 @@
+use crate::geometry::distance::cumulative;
+use crate::stats::gain_loss;
+use crate::types::{Coordinate, Route, TrackSegment};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmoothOptions {
+    /// Treat an elevation of exactly `0` as missing, as written by Google My
+    /// Maps and other tools that have no altitude.
+    pub zero_is_unknown: bool,
+    /// Number of points in the median window used to find spikes. Values
+    /// below 3 disable spike removal.
+    pub median_window: usize,
+    /// A point is a spike when it differs from the median of its window by
+    /// more than this many metres.
+    pub spike_threshold: f64,
+    /// Half-width in metres of the distance-weighted moving average. Zero
+    /// disables smoothing.
+    pub smoothing_distance: f64,
+    /// Threshold passed to [`gain_loss`] for the before and after figures.
+    pub hysteresis: f64,
+}
+
+impl Default for SmoothOptions {
+    fn default() -> Self {
+        Self {
+            zero_is_unknown: false,
+            median_window: 5,
+            spike_threshold: 10.0,
+            smoothing_distance: 50.0,
+            hysteresis: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SmoothStats {
+    pub gain_before: f64,
+    pub loss_before: f64,
+    pub gain_after: f64,
+    pub loss_after: f64,
+    pub spikes_removed: usize,
+    /// Points whose elevation was discarded as unknown.
+    pub unknown: usize,
+}
+
+impl SmoothStats {
+    fn add(&mut self, other: &SmoothStats) {
+        self.gain_before += other.gain_before;
+        self.loss_before += other.loss_before;
+        self.gain_after += other.gain_after;
+        self.loss_after += other.loss_after;
+        self.spikes_removed += other.spikes_removed;
+        self.unknown += other.unknown;
+    }
+}
+
+/// Cleans up elevations on every track segment, and drops zero elevations on
+/// waypoints when `zero_is_unknown` is set.
+pub fn smooth_route(route: &mut Route, options: &SmoothOptions) -> SmoothStats {
+    let mut stats = SmoothStats::default();
+
+    for waypoint in &mut route.waypoints {
+        if clear_unknown(&mut waypoint.coord, options) {
+            stats.unknown += 1;
+        }
+    }
+    for segment in route.tracks.iter_mut().flat_map(|t| t.segments.iter_mut()) {
+        stats.add(&smooth_segment(segment, options));
+    }
+
+    stats
+}
+
+/// Removes spikes with a median filter, then applies a moving average
+/// weighted by distance along the segment. Points without elevation are left
+/// untouched and skipped over.
+pub fn smooth_segment(segment: &mut TrackSegment, options: &SmoothOptions) -> SmoothStats {
+    let mut stats = SmoothStats::default();
+    let (gain, loss) = gain_loss(
+        segment.points.iter().filter_map(|p| p.ele),
+        options.hysteresis,
+    );
+    stats.gain_before = gain;
+    stats.loss_before = loss;
+
+    for point in &mut segment.points {
+        if clear_unknown(point, options) {
+            stats.unknown += 1;
+        }
+    }
+
+    let distances = cumulative(&segment.points);
+    let known: Vec<usize> = (0..segment.points.len())
+        .filter(|&i| segment.points[i].ele.is_some())
+        .collect();
+    let mut elevations: Vec<f64> = known
+        .iter()
+        .map(|&i| segment.points[i].ele.unwrap())
+        .collect();
+    let along: Vec<f64> = known.iter().map(|&i| distances[i]).collect();
+
+    if options.median_window >= 3 {
+        stats.spikes_removed = remove_spikes(&mut elevations, options);
+    }
+    if options.smoothing_distance > 0.0 {
+        elevations = moving_average(&elevations, &along, options.smoothing_distance);
+    }
+
+    for (&i, &ele) in known.iter().zip(&elevations) {
+        segment.points[i].ele = Some(ele);
+    }
+
+    let (gain, loss) = gain_loss(elevations, options.hysteresis);
+    stats.gain_after = gain;
+    stats.loss_after = loss;
+    stats
+}
+
+fn clear_unknown(coord: &mut Coordinate, options: &SmoothOptions) -> bool {
+    if options.zero_is_unknown && coord.ele == Some(0.0) {
+        coord.ele = None;
+        return true;
+    }
+    false
+}
+
+fn remove_spikes(elevations: &mut [f64], options: &SmoothOptions) -> usize {
+    let half = options.median_window / 2;
+    let original = elevations.to_vec();
+    let mut removed = 0;
+
+    for (i, ele) in elevations.iter_mut().enumerate() {
+        let window = &original[i.saturating_sub(half)..(i + half + 1).min(original.len())];
+        if window.len() < 3 {
+            continue;
+        }
+        let median = median(window);
+        if (*ele - median).abs() > options.spike_threshold {
+            *ele = median;
+            removed += 1;
+        }
+    }
+
+    removed
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Triangular kernel: neighbours count less the further along the track they
+/// are, and not at all beyond `radius`.
+fn moving_average(elevations: &[f64], along: &[f64], radius: f64) -> Vec<f64> {
+    let mut start = 0;
+
+    (0..elevations.len())
+        .map(|i| {
+            while along[i] - along[start] > radius {
+                start += 1;
+            }
+
+            let mut total = 0.0;
+            let mut weight = 0.0;
+            for j in start..elevations.len() {
+                let offset = (along[j] - along[i]).abs();
+                if along[j] - along[i] > radius {
+                    break;
+                }
+                let w = 1.0 - offset / radius;
+                total += elevations[j] * w;
+                weight += w;
+            }
+
+            if weight > 0.0 {
+                total / weight
+            } else {
+                elevations[i]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Track, Waypoint};
+
+    // Points roughly 11 m apart heading north.
+    fn segment(elevations: &[f64]) -> TrackSegment {
+        TrackSegment::new(
+            elevations
+                .iter()
+                .enumerate()
+                .map(|(i, &e)| Coordinate::with_elevation(i as f64 * 0.0001, 0.0, e))
+                .collect(),
+        )
+    }
+
+    fn elevations(segment: &TrackSegment) -> Vec<Option<f64>> {
+        segment.points.iter().map(|p| p.ele).collect()
+    }
+
+    #[test]
+    fn test_median_removes_spike() {
+        let mut segment = segment(&[100.0, 101.0, 180.0, 102.0, 103.0]);
+        let options = SmoothOptions {
+            smoothing_distance: 0.0,
+            ..Default::default()
+        };
+
+        let stats = smooth_segment(&mut segment, &options);
+        assert_eq!(stats.spikes_removed, 1);
+        assert_eq!(segment.points[2].ele, Some(102.0));
+        assert_eq!(stats.gain_before, 81.0);
+        assert_eq!(stats.gain_after, 3.0);
+    }
+
+    #[test]
+    fn test_median_keeps_steady_climb() {
+        let mut segment = segment(&[100.0, 110.0, 120.0, 130.0, 140.0]);
+        let options = SmoothOptions {
+            smoothing_distance: 0.0,
+            ..Default::default()
+        };
+
+        let stats = smooth_segment(&mut segment, &options);
+        assert_eq!(stats.spikes_removed, 0);
+        assert_eq!(stats.gain_after, 40.0);
+    }
+
+    #[test]
+    fn test_moving_average_reduces_noise() {
+        let mut segment = segment(&[100.0, 104.0, 100.0, 104.0, 100.0, 104.0, 100.0]);
+        let options = SmoothOptions {
+            median_window: 0,
+            smoothing_distance: 30.0,
+            ..Default::default()
+        };
+
+        let stats = smooth_segment(&mut segment, &options);
+        assert_eq!(stats.gain_before, 12.0);
+        assert!(stats.gain_after < 4.0);
+        for ele in elevations(&segment) {
+            let ele = ele.unwrap();
+            assert!((100.0..=104.0).contains(&ele));
+        }
+    }
+
+    #[test]
+    fn test_moving_average_preserves_linear_profile() {
+        let mut segment = segment(&[0.0, 10.0, 20.0, 30.0, 40.0]);
+        let options = SmoothOptions {
+            median_window: 0,
+            smoothing_distance: 30.0,
+            ..Default::default()
+        };
+
+        smooth_segment(&mut segment, &options);
+        assert!((segment.points[2].ele.unwrap() - 20.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_zero_is_unknown() {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::new(Coordinate::with_elevation(0.0, 0.0, 0.0)));
+        route.add_track(Track::new(vec![segment(&[0.0, 50.0, 0.0, 52.0])]));
+        let options = SmoothOptions {
+            zero_is_unknown: true,
+            median_window: 0,
+            smoothing_distance: 0.0,
+            ..Default::default()
+        };
+
+        let stats = smooth_route(&mut route, &options);
+        assert_eq!(stats.unknown, 3);
+        assert_eq!(stats.gain_before, 102.0);
+        assert_eq!(stats.gain_after, 2.0);
+        assert!(route.waypoints[0].coord.ele.is_none());
+        assert_eq!(
+            elevations(&route.tracks[0].segments[0]),
+            vec![None, Some(50.0), None, Some(52.0)]
+        );
+    }
+
+    #[test]
+    fn test_zero_kept_by_default() {
+        let mut segment = segment(&[0.0, 0.0, 0.0]);
+        let stats = smooth_segment(&mut segment, &SmoothOptions::default());
+        assert_eq!(stats.unknown, 0);
+        assert_eq!(elevations(&segment), vec![Some(0.0); 3]);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::analysis::climbs::{self, ClimbOptions};
+use crate::analysis::cues::{self, CueOptions};
+use crate::geometry::simplify::{simplify_route, SimplifyOptions};
+use crate::transform::markers::{add_markers, MarkerOptions};
+use crate::types::Route;
+use crate::{fit, geojson, gpx, kml, tcx};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Unsupported output format: {0}")]
+    UnsupportedFormat(String),
+    #[error(transparent)]
+    Gpx(#[from] gpx::GpxError),
+    #[error(transparent)]
+    GeoJson(#[from] geojson::GeoJsonError),
+    #[error(transparent)]
+    Kml(#[from] kml::KmlError),
+    #[error(transparent)]
+    Tcx(#[from] tcx::TcxError),
+    #[error(transparent)]
+    Fit(#[from] fit::FitError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Gpx,
+    GeoJson,
+    Kml,
+    Kmz,
+    Tcx,
+    Fit,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Gpx => "gpx",
+            ExportFormat::GeoJson => "geojson",
+            ExportFormat::Kml => "kml",
+            ExportFormat::Kmz => "kmz",
+            ExportFormat::Tcx => "tcx",
+            ExportFormat::Fit => "fit",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Gpx => "application/gpx+xml",
+            ExportFormat::GeoJson => "application/geo+json",
+            ExportFormat::Kml => "application/vnd.google-earth.kml+xml",
+            ExportFormat::Kmz => "application/vnd.google-earth.kmz",
+            ExportFormat::Tcx => "application/vnd.garmin.tcx+xml",
+            ExportFormat::Fit => "application/vnd.ant.fit",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "gpx" => Ok(ExportFormat::Gpx),
+            "geojson" | "json" => Ok(ExportFormat::GeoJson),
+            "kml" => Ok(ExportFormat::Kml),
+            "kmz" => Ok(ExportFormat::Kmz),
+            "tcx" => Ok(ExportFormat::Tcx),
+            "fit" => Ok(ExportFormat::Fit),
+            _ => Err(ExportError::UnsupportedFormat(s.to_string())),
+        }
+    }
+}
+
+/// Transforms applied to a route before it is written out.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConvertOptions {
+    pub simplify: Option<SimplifyOptions>,
+    /// Add a waypoint for each detected turn.
+    pub cues: Option<CueOptions>,
+    /// Add waypoints at the foot and summit of each detected climb.
+    pub climbs: Option<ClimbOptions>,
+    /// Add distance marker waypoints at fixed intervals along each track.
+    pub markers: Option<MarkerOptions>,
+}
+
+impl ConvertOptions {
+    pub fn apply(&self, route: &Route) -> Route {
+        let mut route = route.clone();
+
+        // Turns are detected on the full geometry, before any simplification.
+        if let Some(ref options) = self.cues {
+            let turns = cues::detect(&route, options);
+            route.waypoints.extend(cues::to_waypoints(&turns));
+        }
+
+        if let Some(ref options) = self.climbs {
+            let found = climbs::detect(&route, options);
+            route.waypoints.extend(climbs::to_waypoints(&found));
+        }
+
+        if let Some(ref options) = self.markers {
+            route = add_markers(&route, options);
+        }
+
+        if let Some(ref options) = self.simplify {
+            route = simplify_route(&route, options);
+        }
+
+        route
+    }
+}
+
+pub fn convert(
+    route: &Route,
+    format: ExportFormat,
+    options: &ConvertOptions,
+) -> Result<Vec<u8>, ExportError> {
+    export(&options.apply(route), format)
+}
+
+pub fn export(route: &Route, format: ExportFormat) -> Result<Vec<u8>, ExportError> {
+    let bytes = match format {
+        ExportFormat::Gpx => gpx::write(route)?.into_bytes(),
+        ExportFormat::GeoJson => {
+            geojson::write(route, &geojson::GeoJsonOptions::default())?.into_bytes()
+        }
+        ExportFormat::Kml => kml::write(route, &kml::KmlOptions::default())?.into_bytes(),
+        ExportFormat::Kmz => kml::write_kmz(route, &kml::KmlOptions::default())?,
+        ExportFormat::Tcx => tcx::write(route)?.into_bytes(),
+        ExportFormat::Fit => fit::write(route)?,
+    };
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Coordinate, Track, TrackSegment};
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("GPX".parse::<ExportFormat>().unwrap(), ExportFormat::Gpx);
+        assert_eq!("tcx".parse::<ExportFormat>().unwrap(), ExportFormat::Tcx);
+        assert!("shp".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn test_export_each_format() {
+        let mut route = Route::with_name("Test".to_string());
+        route.add_track(Track::new(vec![TrackSegment::new(vec![Coordinate::new(
+            37.7749, -122.4194,
+        )])]));
+        for format in [
+            ExportFormat::Gpx,
+            ExportFormat::GeoJson,
+            ExportFormat::Kml,
+            ExportFormat::Kmz,
+            ExportFormat::Tcx,
+            ExportFormat::Fit,
+        ] {
+            assert!(!export(&route, format).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_convert_applies_simplification() {
+        let mut route = Route::new();
+        let points = (0..=10)
+            .map(|i| Coordinate::new(0.0, i as f64 * 0.001))
+            .collect();
+        route.add_track(Track::new(vec![TrackSegment::new(points)]));
+
+        let options = ConvertOptions {
+            simplify: Some(SimplifyOptions::default()),
+            ..Default::default()
+        };
+        let gpx = String::from_utf8(convert(&route, ExportFormat::Gpx, &options).unwrap()).unwrap();
+        assert_eq!(gpx.matches("<trkpt").count(), 2);
+    }
+
+    #[test]
+    fn test_convert_adds_cue_waypoints() {
+        let mut route = Route::new();
+        let points = (0..=10)
+            .map(|i| Coordinate::new(0.0, i as f64 * 0.001))
+            .chain((1..=10).map(|i| Coordinate::new(i as f64 * 0.001, 0.01)))
+            .collect();
+        route.add_track(Track::new(vec![TrackSegment::new(points)]));
+
+        let options: ConvertOptions = serde_json::from_str(r#"{"cues": {}}"#).unwrap();
+        let gpx = String::from_utf8(convert(&route, ExportFormat::Gpx, &options).unwrap()).unwrap();
+        assert!(gpx.contains("<name>Turn left at km 1.1</name><sym>Left</sym>"));
+    }
+
+    #[test]
+    fn test_convert_adds_distance_markers() {
+        let mut route = Route::new();
+        let points = vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.03)];
+        route.add_track(Track::new(vec![TrackSegment::new(points)]));
+
+        let options: ConvertOptions =
+            serde_json::from_str(r#"{"markers": {"unit": "miles"}}"#).unwrap();
+        let gpx = String::from_utf8(convert(&route, ExportFormat::Gpx, &options).unwrap()).unwrap();
+        assert!(gpx.contains("<name>mi 2</name>"));
+        assert!(!gpx.contains("<name>mi 3</name>"));
+    }
+
+    #[test]
+    fn test_deserialize_options() {
+        let options: ConvertOptions = serde_json::from_str(
+            r#"{"simplify": {"algorithm": "visvalingam_whyatt", "target_points": 500}}"#,
+        )
+        .unwrap();
+        let simplify = options.simplify.unwrap();
+        assert_eq!(simplify.target_points, Some(500));
+        assert_eq!(simplify.tolerance, 5.0);
+    }
+}
@@ -0,0 +1,50 @@
+pub mod profile;
+mod writer;
+
+pub use writer::{write, FitError};
+
+const CRC_TABLE: [u16; 16] = [
+    0x0000, 0xCC01, 0xD801, 0x1400, 0xF001, 0x3C00, 0x2800, 0xE401, 0xA001, 0x6C00, 0x7800, 0xB401,
+    0x5000, 0x9C01, 0x8801, 0x4400,
+];
+
+/// FIT CRC-16 as used for both the file header and the trailing file CRC.
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        let tmp = CRC_TABLE[(crc & 0xF) as usize];
+        crc = ((crc >> 4) & 0x0FFF) ^ tmp ^ CRC_TABLE[(byte & 0xF) as usize];
+        let tmp = CRC_TABLE[(crc & 0xF) as usize];
+        crc = ((crc >> 4) & 0x0FFF) ^ tmp ^ CRC_TABLE[((byte >> 4) & 0xF) as usize];
+    }
+    crc
+}
+
+pub fn degrees_to_semicircles(degrees: f64) -> i32 {
+    (degrees * (2f64.powi(31) / 180.0)).round() as i32
+}
+
+pub fn semicircles_to_degrees(semicircles: i32) -> f64 {
+    semicircles as f64 * (180.0 / 2f64.powi(31))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc16_of_own_output_is_zero() {
+        let data = b"routes to gpx";
+        let crc = crc16(data);
+        let mut with_crc = data.to_vec();
+        with_crc.extend_from_slice(&crc.to_le_bytes());
+        assert_eq!(crc16(&with_crc), 0);
+    }
+
+    #[test]
+    fn test_semicircle_round_trip() {
+        let semicircles = degrees_to_semicircles(-122.4194);
+        assert!((semicircles_to_degrees(semicircles) - (-122.4194)).abs() < 1e-7);
+        assert_eq!(degrees_to_semicircles(90.0), 1 << 30);
+    }
+}
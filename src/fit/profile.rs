@@ -0,0 +1,78 @@
+// Subset of the FIT SDK profile used by the course encoder and decoder.
+
+pub const HEADER_SIZE: u8 = 14;
+pub const PROTOCOL_VERSION: u8 = 0x20;
+pub const PROFILE_VERSION: u16 = 2132;
+pub const DATA_TYPE: &[u8; 4] = b".FIT";
+
+/// Seconds between the Unix epoch and the FIT epoch (1989-12-31T00:00:00Z).
+pub const FIT_EPOCH_OFFSET: i64 = 631_065_600;
+
+pub const MESG_FILE_ID: u16 = 0;
+pub const MESG_LAP: u16 = 19;
+pub const MESG_RECORD: u16 = 20;
+pub const MESG_EVENT: u16 = 21;
+pub const MESG_COURSE: u16 = 31;
+pub const MESG_COURSE_POINT: u16 = 32;
+
+pub const FILE_TYPE_COURSE: u8 = 6;
+pub const MANUFACTURER_DEVELOPMENT: u16 = 255;
+pub const SPORT_GENERIC: u8 = 0;
+pub const COURSE_POINT_GENERIC: u8 = 0;
+pub const EVENT_TIMER: u8 = 0;
+pub const EVENT_TYPE_START: u8 = 0;
+pub const EVENT_TYPE_STOP_ALL: u8 = 4;
+
+pub const FIELD_TIMESTAMP: u8 = 253;
+pub const FIELD_MESSAGE_INDEX: u8 = 254;
+
+pub const FILE_ID_TYPE: u8 = 0;
+pub const FILE_ID_MANUFACTURER: u8 = 1;
+pub const FILE_ID_PRODUCT: u8 = 2;
+pub const FILE_ID_TIME_CREATED: u8 = 4;
+
+pub const COURSE_SPORT: u8 = 4;
+pub const COURSE_NAME: u8 = 5;
+
+pub const LAP_START_TIME: u8 = 2;
+pub const LAP_START_POSITION_LAT: u8 = 3;
+pub const LAP_START_POSITION_LONG: u8 = 4;
+pub const LAP_END_POSITION_LAT: u8 = 5;
+pub const LAP_END_POSITION_LONG: u8 = 6;
+pub const LAP_TOTAL_ELAPSED_TIME: u8 = 7;
+pub const LAP_TOTAL_TIMER_TIME: u8 = 8;
+pub const LAP_TOTAL_DISTANCE: u8 = 9;
+
+pub const EVENT_EVENT: u8 = 0;
+pub const EVENT_EVENT_TYPE: u8 = 1;
+
+pub const RECORD_POSITION_LAT: u8 = 0;
+pub const RECORD_POSITION_LONG: u8 = 1;
+pub const RECORD_ALTITUDE: u8 = 2;
+pub const RECORD_DISTANCE: u8 = 5;
+pub const RECORD_ENHANCED_ALTITUDE: u8 = 78;
+
+pub const COURSE_POINT_TIMESTAMP: u8 = 1;
+pub const COURSE_POINT_POSITION_LAT: u8 = 2;
+pub const COURSE_POINT_POSITION_LONG: u8 = 3;
+pub const COURSE_POINT_DISTANCE: u8 = 4;
+pub const COURSE_POINT_TYPE: u8 = 5;
+pub const COURSE_POINT_NAME: u8 = 6;
+
+pub const BASE_ENUM: u8 = 0x00;
+pub const BASE_UINT8: u8 = 0x02;
+pub const BASE_STRING: u8 = 0x07;
+pub const BASE_UINT16: u8 = 0x84;
+pub const BASE_SINT32: u8 = 0x85;
+pub const BASE_UINT32: u8 = 0x86;
+
+/// Scale and offset applied to `altitude` fields (metres).
+pub const ALTITUDE_SCALE: f64 = 5.0;
+pub const ALTITUDE_OFFSET: f64 = 500.0;
+/// Scale applied to `distance` fields (metres).
+pub const DISTANCE_SCALE: f64 = 100.0;
+/// Scale applied to lap time fields (seconds).
+pub const TIME_SCALE: f64 = 1000.0;
+
+/// Size of the fixed-width string fields the encoder writes for names.
+pub const NAME_SIZE: usize = 16;
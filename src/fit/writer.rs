@@ -0,0 +1,593 @@
+use super::profile::*;
+use super::{crc16, degrees_to_semicircles};
+use crate::geometry::distance::cumulative_segments;
+use crate::geometry::project::project;
+use crate::transform::timestamps::{ensure_timestamps, SYNTHETIC_START};
+use crate::types::{Coordinate, Route};
+use thiserror::Error;
+
+const LOCAL_FILE_ID: u8 = 0;
+const LOCAL_COURSE: u8 = 1;
+const LOCAL_LAP: u8 = 2;
+const LOCAL_RECORD: u8 = 3;
+const LOCAL_COURSE_POINT: u8 = 4;
+const LOCAL_EVENT: u8 = 5;
+
+#[derive(Error, Debug)]
+pub enum FitError {
+    #[error("Route has no track points to build a course from")]
+    EmptyRoute,
+    #[error("Too many course points: {0} (FIT allows at most 65535)")]
+    TooManyCoursePoints(usize),
+    #[error("Time {0} ms since the Unix epoch cannot be represented in FIT")]
+    TimeOutOfRange(i64),
+}
+
+struct Field {
+    number: u8,
+    base_type: u8,
+    data: Vec<u8>,
+}
+
+impl Field {
+    fn enumeration(number: u8, value: u8) -> Self {
+        Self {
+            number,
+            base_type: BASE_ENUM,
+            data: vec![value],
+        }
+    }
+
+    fn uint16(number: u8, value: u16) -> Self {
+        Self {
+            number,
+            base_type: BASE_UINT16,
+            data: value.to_le_bytes().to_vec(),
+        }
+    }
+
+    fn uint32(number: u8, value: u32) -> Self {
+        Self {
+            number,
+            base_type: BASE_UINT32,
+            data: value.to_le_bytes().to_vec(),
+        }
+    }
+
+    fn sint32(number: u8, value: i32) -> Self {
+        Self {
+            number,
+            base_type: BASE_SINT32,
+            data: value.to_le_bytes().to_vec(),
+        }
+    }
+
+    fn string(number: u8, value: &str, size: usize) -> Self {
+        let mut data = Vec::with_capacity(size);
+        for c in value.chars() {
+            if data.len() + c.len_utf8() >= size {
+                break;
+            }
+            let mut buf = [0u8; 4];
+            data.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        }
+        data.resize(size, 0);
+        Self {
+            number,
+            base_type: BASE_STRING,
+            data,
+        }
+    }
+}
+
+/// Global message number plus `(field number, size, base type)` per field.
+type Definition = (u16, Vec<(u8, usize, u8)>);
+
+/// Writes FIT records, emitting a new definition message whenever the field
+/// layout of a local message type changes.
+struct Encoder {
+    data: Vec<u8>,
+    definitions: [Option<Definition>; 16],
+}
+
+impl Encoder {
+    fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            definitions: Default::default(),
+        }
+    }
+
+    fn write_message(&mut self, local: u8, global: u16, fields: &[Field]) {
+        let layout: Vec<(u8, usize, u8)> = fields
+            .iter()
+            .map(|f| (f.number, f.data.len(), f.base_type))
+            .collect();
+
+        let slot = &mut self.definitions[local as usize];
+        if slot.as_ref() != Some(&(global, layout.clone())) {
+            self.data.push(0x40 | local);
+            self.data.push(0);
+            self.data.push(0);
+            self.data.extend_from_slice(&global.to_le_bytes());
+            self.data.push(layout.len() as u8);
+            for (number, size, base_type) in &layout {
+                self.data
+                    .extend_from_slice(&[*number, *size as u8, *base_type]);
+            }
+            *slot = Some((global, layout));
+        }
+
+        self.data.push(local);
+        for field in fields {
+            self.data.extend_from_slice(&field.data);
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut file = Vec::with_capacity(HEADER_SIZE as usize + self.data.len() + 2);
+        file.push(HEADER_SIZE);
+        file.push(PROTOCOL_VERSION);
+        file.extend_from_slice(&PROFILE_VERSION.to_le_bytes());
+        file.extend_from_slice(&(self.data.len() as u32).to_le_bytes());
+        file.extend_from_slice(DATA_TYPE);
+        let header_crc = crc16(&file);
+        file.extend_from_slice(&header_crc.to_le_bytes());
+
+        file.extend_from_slice(&self.data);
+        let file_crc = crc16(&file);
+        file.extend_from_slice(&file_crc.to_le_bytes());
+        file
+    }
+}
+
+/// Encodes the route as a FIT course file. All tracks are joined into a
+/// single course line and waypoints become course points on that line.
+/// Distances do not count the gaps between segments. Devices require a
+/// timestamp on every record, so points without recorded times are given
+/// filled-in ones.
+pub fn write(route: &Route) -> Result<Vec<u8>, FitError> {
+    let route = &ensure_timestamps(route);
+    let points: Vec<Coordinate> = route
+        .tracks
+        .iter()
+        .flat_map(|track| track.segments.iter())
+        .flat_map(|segment| segment.points.iter().cloned())
+        .collect();
+
+    if points.is_empty() {
+        return Err(FitError::EmptyRoute);
+    }
+
+    let distances = cumulative_segments(route.tracks.iter().flat_map(|t| &t.segments));
+    let total = distances.last().copied().unwrap_or(0.0);
+    let first = &points[0];
+    let last = &points[points.len() - 1];
+    let start = encode_time(first)?;
+    let end = encode_time(last)?;
+    let duration = encode_duration(end.saturating_sub(start));
+    let mut encoder = Encoder::new();
+
+    encoder.write_message(
+        LOCAL_FILE_ID,
+        MESG_FILE_ID,
+        &[
+            Field::enumeration(FILE_ID_TYPE, FILE_TYPE_COURSE),
+            Field::uint16(FILE_ID_MANUFACTURER, MANUFACTURER_DEVELOPMENT),
+            Field::uint16(FILE_ID_PRODUCT, 0),
+            Field::uint32(FILE_ID_TIME_CREATED, start),
+        ],
+    );
+
+    encoder.write_message(
+        LOCAL_COURSE,
+        MESG_COURSE,
+        &[
+            Field::string(
+                COURSE_NAME,
+                route.name.as_deref().unwrap_or("Route"),
+                NAME_SIZE,
+            ),
+            Field::enumeration(COURSE_SPORT, SPORT_GENERIC),
+        ],
+    );
+
+    encoder.write_message(
+        LOCAL_LAP,
+        MESG_LAP,
+        &[
+            Field::uint32(FIELD_TIMESTAMP, end),
+            Field::uint32(LAP_START_TIME, start),
+            Field::sint32(LAP_START_POSITION_LAT, degrees_to_semicircles(first.lat)),
+            Field::sint32(LAP_START_POSITION_LONG, degrees_to_semicircles(first.lon)),
+            Field::sint32(LAP_END_POSITION_LAT, degrees_to_semicircles(last.lat)),
+            Field::sint32(LAP_END_POSITION_LONG, degrees_to_semicircles(last.lon)),
+            Field::uint32(LAP_TOTAL_ELAPSED_TIME, duration),
+            Field::uint32(LAP_TOTAL_TIMER_TIME, duration),
+            Field::uint32(LAP_TOTAL_DISTANCE, encode_distance(total)),
+        ],
+    );
+
+    write_timer_event(&mut encoder, start, EVENT_TYPE_START);
+
+    for (point, distance) in points.iter().zip(&distances) {
+        let mut fields = vec![
+            Field::uint32(FIELD_TIMESTAMP, encode_time(point)?),
+            Field::sint32(RECORD_POSITION_LAT, degrees_to_semicircles(point.lat)),
+            Field::sint32(RECORD_POSITION_LONG, degrees_to_semicircles(point.lon)),
+            Field::uint32(RECORD_DISTANCE, encode_distance(*distance)),
+        ];
+        if let Some(ele) = point.ele {
+            fields.push(Field::uint16(RECORD_ALTITUDE, encode_altitude(ele)));
+        }
+        encoder.write_message(LOCAL_RECORD, MESG_RECORD, &fields);
+    }
+
+    write_timer_event(&mut encoder, end, EVENT_TYPE_STOP_ALL);
+
+    let mut course_points: Vec<_> = route
+        .waypoints
+        .iter()
+        .filter_map(|waypoint| project(&points, &waypoint.coord).map(|p| (waypoint, p)))
+        .collect();
+    course_points.sort_by(|a, b| a.1.distance_along.total_cmp(&b.1.distance_along));
+    // 0xFFFF is the invalid value, so indices stop at 65534.
+    if course_points.len() > u16::MAX as usize {
+        return Err(FitError::TooManyCoursePoints(course_points.len()));
+    }
+
+    for (index, (waypoint, projection)) in course_points.iter().enumerate() {
+        let time = encode_time(&projection.point)?;
+        // Measured along the segments, so a point on a gap adds nothing.
+        let distance = match distances.get(projection.index + 1) {
+            Some(next) => {
+                let from = distances[projection.index];
+                from + (next - from) * projection.fraction
+            }
+            None => distances[projection.index],
+        };
+        encoder.write_message(
+            LOCAL_COURSE_POINT,
+            MESG_COURSE_POINT,
+            &[
+                Field::uint16(FIELD_MESSAGE_INDEX, index as u16),
+                Field::uint32(COURSE_POINT_TIMESTAMP, time),
+                Field::sint32(
+                    COURSE_POINT_POSITION_LAT,
+                    degrees_to_semicircles(projection.point.lat),
+                ),
+                Field::sint32(
+                    COURSE_POINT_POSITION_LONG,
+                    degrees_to_semicircles(projection.point.lon),
+                ),
+                Field::uint32(COURSE_POINT_DISTANCE, encode_distance(distance)),
+                Field::enumeration(COURSE_POINT_TYPE, COURSE_POINT_GENERIC),
+                Field::string(
+                    COURSE_POINT_NAME,
+                    waypoint.name.as_deref().unwrap_or(""),
+                    NAME_SIZE,
+                ),
+            ],
+        );
+    }
+
+    Ok(encoder.finish())
+}
+
+fn write_timer_event(encoder: &mut Encoder, timestamp: u32, event_type: u8) {
+    encoder.write_message(
+        LOCAL_EVENT,
+        MESG_EVENT,
+        &[
+            Field::uint32(FIELD_TIMESTAMP, timestamp),
+            Field::enumeration(EVENT_EVENT, EVENT_TIMER),
+            Field::enumeration(EVENT_EVENT_TYPE, event_type),
+        ],
+    );
+}
+
+/// A point's time in seconds since the FIT epoch. Every point has a time
+/// once the route has been through `ensure_timestamps`.
+fn encode_time(point: &Coordinate) -> Result<u32, FitError> {
+    let millis = point.time.unwrap_or(SYNTHETIC_START);
+    (millis.div_euclid(1000) - FIT_EPOCH_OFFSET)
+        .try_into()
+        .ok()
+        .filter(|&t: &u32| t != u32::MAX)
+        .ok_or(FitError::TimeOutOfRange(millis))
+}
+
+fn encode_duration(seconds: u32) -> u32 {
+    (seconds as f64 * TIME_SCALE).clamp(0.0, (u32::MAX - 1) as f64) as u32
+}
+
+fn encode_distance(metres: f64) -> u32 {
+    (metres * DISTANCE_SCALE)
+        .round()
+        .clamp(0.0, (u32::MAX - 1) as f64) as u32
+}
+
+fn encode_altitude(metres: f64) -> u16 {
+    ((metres + ALTITUDE_OFFSET) * ALTITUDE_SCALE)
+        .round()
+        .clamp(0.0, (u16::MAX - 1) as f64) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::semicircles_to_degrees;
+    use super::*;
+    use crate::geometry::distance::{cumulative, length};
+    use crate::types::{Track, TrackSegment, Waypoint};
+    use std::collections::HashMap;
+
+    struct Message {
+        global: u16,
+        fields: HashMap<u8, Vec<u8>>,
+    }
+
+    // Generic decoder for normal-header messages, enough to check the
+    // encoder's output without relying on the encoder's own bookkeeping.
+    fn decode(file: &[u8]) -> Vec<Message> {
+        assert_eq!(file[0], HEADER_SIZE);
+        assert_eq!(&file[8..12], DATA_TYPE);
+        assert_eq!(crc16(&file[..14]), 0);
+        assert_eq!(crc16(file), 0);
+
+        let data_size = u32::from_le_bytes([file[4], file[5], file[6], file[7]]) as usize;
+        let data = &file[14..14 + data_size];
+        assert_eq!(file.len(), 14 + data_size + 2);
+
+        let mut definitions: HashMap<u8, (u16, Vec<(u8, usize)>)> = HashMap::new();
+        let mut messages = Vec::new();
+        let mut i = 0;
+
+        while i < data.len() {
+            let header = data[i];
+            i += 1;
+            let local = header & 0x0F;
+            if header & 0x40 != 0 {
+                let global = u16::from_le_bytes([data[i + 2], data[i + 3]]);
+                let count = data[i + 4] as usize;
+                i += 5;
+                let fields = (0..count)
+                    .map(|n| (data[i + n * 3], data[i + n * 3 + 1] as usize))
+                    .collect();
+                i += count * 3;
+                definitions.insert(local, (global, fields));
+            } else {
+                let (global, layout) = &definitions[&local];
+                let mut fields = HashMap::new();
+                for (number, size) in layout {
+                    fields.insert(*number, data[i..i + size].to_vec());
+                    i += size;
+                }
+                messages.push(Message {
+                    global: *global,
+                    fields,
+                });
+            }
+        }
+
+        messages
+    }
+
+    fn read_i32(bytes: &[u8]) -> i32 {
+        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn read_u32(bytes: &[u8]) -> u32 {
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn sample_route() -> Route {
+        let mut route = Route::with_name("Hill Repeats".to_string());
+        let segment = TrackSegment::new(vec![
+            Coordinate::with_elevation(0.0, 0.0, 10.0),
+            Coordinate::with_elevation(0.0, 0.01, 20.0),
+            Coordinate::with_elevation(0.0, 0.02, 15.0),
+        ]);
+        route.add_track(Track::new(vec![segment]));
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.001, 0.015),
+            "Summit".to_string(),
+        ));
+        route
+    }
+
+    #[test]
+    fn test_round_trip_messages() {
+        let file = write(&sample_route()).unwrap();
+        let messages = decode(&file);
+
+        let globals: Vec<u16> = messages.iter().map(|m| m.global).collect();
+        assert_eq!(
+            globals,
+            vec![
+                MESG_FILE_ID,
+                MESG_COURSE,
+                MESG_LAP,
+                MESG_EVENT,
+                MESG_RECORD,
+                MESG_RECORD,
+                MESG_RECORD,
+                MESG_EVENT,
+                MESG_COURSE_POINT
+            ]
+        );
+
+        assert_eq!(messages[0].fields[&FILE_ID_TYPE], vec![FILE_TYPE_COURSE]);
+        assert!(messages[1].fields[&COURSE_NAME].starts_with(b"Hill Repeats\0"));
+    }
+
+    #[test]
+    fn test_round_trip_records() {
+        let route = sample_route();
+        let file = write(&route).unwrap();
+        let records: Vec<Message> = decode(&file)
+            .into_iter()
+            .filter(|m| m.global == MESG_RECORD)
+            .collect();
+
+        let points = &route.tracks[0].segments[0].points;
+        let distances = cumulative(points);
+        for ((record, point), distance) in records.iter().zip(points).zip(&distances) {
+            let lat = semicircles_to_degrees(read_i32(&record.fields[&RECORD_POSITION_LAT]));
+            let lon = semicircles_to_degrees(read_i32(&record.fields[&RECORD_POSITION_LONG]));
+            assert!((lat - point.lat).abs() < 1e-7);
+            assert!((lon - point.lon).abs() < 1e-7);
+
+            let altitude = &record.fields[&RECORD_ALTITUDE];
+            let altitude = u16::from_le_bytes([altitude[0], altitude[1]]) as f64;
+            assert!((altitude / ALTITUDE_SCALE - ALTITUDE_OFFSET - point.ele.unwrap()).abs() < 0.2);
+
+            let encoded = read_u32(&record.fields[&RECORD_DISTANCE]) as f64 / DISTANCE_SCALE;
+            assert!((encoded - distance).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_gaps_between_segments_do_not_add_distance() {
+        let mut route = sample_route();
+        let second = vec![Coordinate::new(0.0, 0.05), Coordinate::new(0.0, 0.06)];
+        route.tracks[0]
+            .segments
+            .push(TrackSegment::new(second.clone()));
+        route.waypoints[0].coord = Coordinate::new(0.001, 0.055);
+        let first = length(&route.tracks[0].segments[0].points);
+        let total = first + length(&second);
+
+        let messages = decode(&write(&route).unwrap());
+        let distance = |m: &Message, field| read_u32(&m.fields[&field]) as f64 / DISTANCE_SCALE;
+        let records: Vec<&Message> = messages
+            .iter()
+            .filter(|m| m.global == MESG_RECORD)
+            .collect();
+        assert!((distance(records[3], RECORD_DISTANCE) - first).abs() < 0.01);
+        assert!((distance(records[4], RECORD_DISTANCE) - total).abs() < 0.01);
+
+        let lap = messages.iter().find(|m| m.global == MESG_LAP).unwrap();
+        assert!((distance(lap, LAP_TOTAL_DISTANCE) - total).abs() < 0.01);
+
+        let point = messages.last().unwrap();
+        let along = distance(point, COURSE_POINT_DISTANCE);
+        assert!((along - (first + length(&second) / 2.0)).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_round_trip_course_point() {
+        let file = write(&sample_route()).unwrap();
+        let messages = decode(&file);
+        let point = messages.last().unwrap();
+
+        assert!(point.fields[&COURSE_POINT_NAME].starts_with(b"Summit\0"));
+        let lat = semicircles_to_degrees(read_i32(&point.fields[&COURSE_POINT_POSITION_LAT]));
+        let lon = semicircles_to_degrees(read_i32(&point.fields[&COURSE_POINT_POSITION_LONG]));
+        assert!(lat.abs() < 1e-7);
+        assert!((lon - 0.015).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_writes_synthetic_timestamps() {
+        let messages = decode(&write(&sample_route()).unwrap());
+        let timestamp = |m: &Message, field| read_u32(&m.fields[&field]);
+
+        let records: Vec<&Message> = messages
+            .iter()
+            .filter(|m| m.global == MESG_RECORD)
+            .collect();
+        let start = timestamp(records[0], FIELD_TIMESTAMP);
+        let end = timestamp(records[2], FIELD_TIMESTAMP);
+        assert_eq!(start as i64, SYNTHETIC_START / 1000 - FIT_EPOCH_OFFSET);
+        assert!(timestamp(records[1], FIELD_TIMESTAMP) > start);
+        assert!(end > timestamp(records[1], FIELD_TIMESTAMP));
+
+        let lap = messages.iter().find(|m| m.global == MESG_LAP).unwrap();
+        assert_eq!(timestamp(lap, LAP_START_TIME), start);
+        assert_eq!(timestamp(lap, FIELD_TIMESTAMP), end);
+        assert_eq!(timestamp(lap, LAP_TOTAL_TIMER_TIME), (end - start) * 1000);
+
+        let events: Vec<&Message> = messages.iter().filter(|m| m.global == MESG_EVENT).collect();
+        assert_eq!(events[0].fields[&EVENT_EVENT_TYPE], vec![EVENT_TYPE_START]);
+        assert_eq!(timestamp(events[0], FIELD_TIMESTAMP), start);
+        assert_eq!(
+            events[1].fields[&EVENT_EVENT_TYPE],
+            vec![EVENT_TYPE_STOP_ALL]
+        );
+        assert_eq!(timestamp(events[1], FIELD_TIMESTAMP), end);
+
+        let point = messages.last().unwrap();
+        let time = timestamp(point, COURSE_POINT_TIMESTAMP);
+        assert!(time > timestamp(records[1], FIELD_TIMESTAMP) && time < end);
+    }
+
+    #[test]
+    fn test_uses_recorded_timestamps() {
+        let mut route = sample_route();
+        for (i, point) in route.tracks[0].segments[0].points.iter_mut().enumerate() {
+            point.time = Some(1_714_552_200_000 + i as i64 * 60_000);
+        }
+
+        let messages = decode(&write(&route).unwrap());
+        let record = messages.iter().find(|m| m.global == MESG_RECORD).unwrap();
+        assert_eq!(
+            read_u32(&record.fields[&FIELD_TIMESTAMP]) as i64,
+            1_714_552_200 - FIT_EPOCH_OFFSET
+        );
+    }
+
+    #[test]
+    fn test_time_before_fit_epoch() {
+        let mut route = sample_route();
+        for point in &mut route.tracks[0].segments[0].points {
+            point.time = Some(0);
+        }
+        assert!(matches!(write(&route), Err(FitError::TimeOutOfRange(0))));
+    }
+
+    #[test]
+    fn test_too_many_course_points() {
+        let mut route = sample_route();
+        for _ in 0..u16::MAX {
+            route.add_waypoint(Waypoint::new(Coordinate::new(0.0, 0.005)));
+        }
+        assert!(matches!(
+            write(&route),
+            Err(FitError::TooManyCoursePoints(65536))
+        ));
+    }
+
+    #[test]
+    fn test_redefines_record_layout_when_altitude_changes() {
+        let mut route = Route::new();
+        let segment = TrackSegment::new(vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::with_elevation(0.0, 0.01, 5.0),
+        ]);
+        route.add_track(Track::new(vec![segment]));
+
+        let messages = decode(&write(&route).unwrap());
+        let records: Vec<&Message> = messages
+            .iter()
+            .filter(|m| m.global == MESG_RECORD)
+            .collect();
+        assert!(!records[0].fields.contains_key(&RECORD_ALTITUDE));
+        assert!(records[1].fields.contains_key(&RECORD_ALTITUDE));
+    }
+
+    #[test]
+    fn test_truncates_long_names() {
+        let field = Field::string(
+            COURSE_NAME,
+            "A name far longer than sixteen bytes",
+            NAME_SIZE,
+        );
+        assert_eq!(field.data.len(), NAME_SIZE);
+        assert_eq!(field.data[NAME_SIZE - 1], 0);
+    }
+
+    #[test]
+    fn test_empty_route() {
+        assert!(matches!(write(&Route::new()), Err(FitError::EmptyRoute)));
+    }
+}
@@ -0,0 +1,265 @@
+use crate::geometry::project::interpolate;
+use crate::types::{Coordinate, Route, Track, Waypoint};
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GeoJsonError {
+    #[error("JSON writing error: {0}")]
+    JsonError(String),
+}
+
+impl From<serde_json::Error> for GeoJsonError {
+    fn from(e: serde_json::Error) -> Self {
+        GeoJsonError::JsonError(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GeoJsonOptions {
+    /// Split lines that cross the antimeridian into separate parts, as
+    /// recommended by RFC 7946 section 3.1.9.
+    pub split_antimeridian: bool,
+}
+
+pub fn write(route: &Route, options: &GeoJsonOptions) -> Result<String, GeoJsonError> {
+    Ok(serde_json::to_string(&to_value(route, options))?)
+}
+
+pub fn to_value(route: &Route, options: &GeoJsonOptions) -> Value {
+    let mut features = Vec::new();
+
+    for (index, waypoint) in route.waypoints.iter().enumerate() {
+        features.push(waypoint_feature(waypoint, index));
+    }
+
+    for (index, track) in route.tracks.iter().enumerate() {
+        if let Some(feature) = track_feature(track, index, options) {
+            features.push(feature);
+        }
+    }
+
+    let mut collection = Map::new();
+    collection.insert("type".to_string(), json!("FeatureCollection"));
+    if let Some(ref name) = route.name {
+        collection.insert("name".to_string(), json!(name));
+    }
+    collection.insert("features".to_string(), Value::Array(features));
+
+    Value::Object(collection)
+}
+
+fn waypoint_feature(waypoint: &Waypoint, index: usize) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Point",
+            "coordinates": position(&waypoint.coord),
+        },
+        "properties": {
+            "kind": "waypoint",
+            "index": index,
+            "name": waypoint.name,
+        },
+    })
+}
+
+fn track_feature(track: &Track, index: usize, options: &GeoJsonOptions) -> Option<Value> {
+    let mut lines: Vec<Vec<Value>> = Vec::new();
+
+    for segment in &track.segments {
+        if segment.points.len() < 2 {
+            continue;
+        }
+
+        if options.split_antimeridian {
+            lines.extend(split_antimeridian(&segment.points));
+        } else {
+            lines.push(segment.points.iter().map(position).collect());
+        }
+    }
+
+    let geometry = match lines.len() {
+        0 => return None,
+        1 => json!({
+            "type": "LineString",
+            "coordinates": lines.remove(0),
+        }),
+        _ => json!({
+            "type": "MultiLineString",
+            "coordinates": lines,
+        }),
+    };
+
+    Some(json!({
+        "type": "Feature",
+        "geometry": geometry,
+        "properties": {
+            "kind": "track",
+            "index": index,
+            "name": track.name,
+        },
+    }))
+}
+
+fn position(coord: &Coordinate) -> Value {
+    match coord.ele {
+        Some(ele) => json!([coord.lon, coord.lat, ele]),
+        None => json!([coord.lon, coord.lat]),
+    }
+}
+
+fn split_antimeridian(points: &[Coordinate]) -> Vec<Vec<Value>> {
+    let mut parts = Vec::new();
+    let mut current = vec![position(&points[0])];
+
+    for pair in points.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+        let delta = to.lon - from.lon;
+
+        if delta.abs() > 180.0 {
+            // Unwrap the destination longitude so the crossing can be
+            // interpolated along the short way round.
+            let edge = if delta < 0.0 { 180.0 } else { -180.0 };
+            let unwrapped = if delta < 0.0 {
+                to.lon + 360.0
+            } else {
+                to.lon - 360.0
+            };
+            let fraction = (edge - from.lon) / (unwrapped - from.lon);
+            let mut crossing = interpolate(from, to, fraction);
+
+            crossing.lon = edge;
+            current.push(position(&crossing));
+            parts.push(std::mem::take(&mut current));
+            crossing.lon = -edge;
+            current.push(position(&crossing));
+        }
+
+        current.push(position(to));
+    }
+
+    parts.push(current);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TrackSegment;
+
+    #[test]
+    fn test_write_feature_collection() {
+        let route = Route::with_name("Test Route".to_string());
+        let geojson = to_value(&route, &GeoJsonOptions::default());
+        assert_eq!(geojson["type"], "FeatureCollection");
+        assert_eq!(geojson["name"], "Test Route");
+        assert!(geojson["features"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_write_waypoint_point() {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::with_elevation(37.7749, -122.4194, 12.0),
+            "San Francisco".to_string(),
+        ));
+
+        let geojson = to_value(&route, &GeoJsonOptions::default());
+        let feature = &geojson["features"][0];
+        assert_eq!(feature["geometry"]["type"], "Point");
+        assert_eq!(
+            feature["geometry"]["coordinates"],
+            json!([-122.4194, 37.7749, 12.0])
+        );
+        assert_eq!(feature["properties"]["name"], "San Francisco");
+        assert_eq!(feature["properties"]["kind"], "waypoint");
+    }
+
+    #[test]
+    fn test_write_track_line_string() {
+        let mut route = Route::new();
+        let segment = TrackSegment::new(vec![
+            Coordinate::new(37.7749, -122.4194),
+            Coordinate::new(37.7835, -122.4089),
+        ]);
+        route.add_track(Track::with_name("My Ride".to_string(), vec![segment]));
+
+        let geojson = to_value(&route, &GeoJsonOptions::default());
+        let feature = &geojson["features"][0];
+        assert_eq!(feature["geometry"]["type"], "LineString");
+        assert_eq!(
+            feature["geometry"]["coordinates"][1],
+            json!([-122.4089, 37.7835])
+        );
+        assert_eq!(feature["properties"]["name"], "My Ride");
+    }
+
+    #[test]
+    fn test_write_track_multi_line_string() {
+        let mut route = Route::new();
+        let segment1 = TrackSegment::new(vec![
+            Coordinate::new(37.7749, -122.4194),
+            Coordinate::new(37.7835, -122.4089),
+        ]);
+        let segment2 = TrackSegment::new(vec![
+            Coordinate::new(40.7128, -74.0060),
+            Coordinate::new(40.7580, -73.9855),
+        ]);
+        route.add_track(Track::new(vec![segment1, segment2]));
+
+        let geojson = to_value(&route, &GeoJsonOptions::default());
+        let geometry = &geojson["features"][0]["geometry"];
+        assert_eq!(geometry["type"], "MultiLineString");
+        assert_eq!(geometry["coordinates"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_skip_single_point_segments() {
+        let mut route = Route::new();
+        let segment = TrackSegment::new(vec![Coordinate::new(37.7749, -122.4194)]);
+        route.add_track(Track::new(vec![segment]));
+
+        let geojson = to_value(&route, &GeoJsonOptions::default());
+        assert!(geojson["features"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_split_antimeridian() {
+        let mut route = Route::new();
+        let segment = TrackSegment::new(vec![
+            Coordinate::new(-17.0, 178.0),
+            Coordinate::new(-19.0, -178.0),
+        ]);
+        route.add_track(Track::new(vec![segment]));
+
+        let options = GeoJsonOptions {
+            split_antimeridian: true,
+        };
+        let geojson = to_value(&route, &options);
+        let geometry = &geojson["features"][0]["geometry"];
+        assert_eq!(geometry["type"], "MultiLineString");
+        assert_eq!(geometry["coordinates"][0][1], json!([180.0, -18.0]));
+        assert_eq!(geometry["coordinates"][1][0], json!([-180.0, -18.0]));
+    }
+
+    #[test]
+    fn test_no_split_without_option() {
+        let mut route = Route::new();
+        let segment = TrackSegment::new(vec![
+            Coordinate::new(-17.0, 178.0),
+            Coordinate::new(-19.0, -178.0),
+        ]);
+        route.add_track(Track::new(vec![segment]));
+
+        let geojson = to_value(&route, &GeoJsonOptions::default());
+        assert_eq!(geojson["features"][0]["geometry"]["type"], "LineString");
+    }
+
+    #[test]
+    fn test_write_string() {
+        let route = Route::new();
+        let geojson = write(&route, &GeoJsonOptions::default()).unwrap();
+        assert!(geojson.contains("\"FeatureCollection\""));
+    }
+}
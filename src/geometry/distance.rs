@@ -0,0 +1,284 @@
+use crate::types::{Coordinate, TrackSegment};
+
+/// Mean Earth radius in metres, as used by the haversine formula.
+pub const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Great-circle distance between two coordinates in metres.
+pub fn haversine(a: &Coordinate, b: &Coordinate) -> f64 {
+    let lat1 = a.lat.to_radians();
+    let lat2 = b.lat.to_radians();
+    let dlat = (b.lat - a.lat).to_radians();
+    let dlon = (b.lon - a.lon).to_radians();
+
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * h.sqrt().asin()
+}
+
+/// Geodesic distance on the WGS-84 ellipsoid in metres, using Vincenty's
+/// inverse formula. Returns `None` when the iteration fails to converge,
+/// which only happens for nearly antipodal points.
+pub fn vincenty(a: &Coordinate, b: &Coordinate) -> Option<f64> {
+    const A: f64 = 6_378_137.0;
+    const F: f64 = 1.0 / 298.257_223_563;
+    const B: f64 = A * (1.0 - F);
+
+    let l = (b.lon - a.lon).to_radians();
+    let u1 = ((1.0 - F) * a.lat.to_radians().tan()).atan();
+    let u2 = ((1.0 - F) * b.lat.to_radians().tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..200 {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+        .sqrt();
+        if sin_sigma == 0.0 {
+            return Some(0.0);
+        }
+
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos_sq_alpha = 1.0 - sin_alpha * sin_alpha;
+        let cos_2sigma_m = if cos_sq_alpha == 0.0 {
+            0.0
+        } else {
+            cos_sigma - 2.0 * sin_u1 * sin_u2 / cos_sq_alpha
+        };
+        let c = F / 16.0 * cos_sq_alpha * (4.0 + F * (4.0 - 3.0 * cos_sq_alpha));
+
+        let previous = lambda;
+        lambda = l
+            + (1.0 - c)
+                * F
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))));
+
+        if (lambda - previous).abs() < 1e-12 {
+            let u_sq = cos_sq_alpha * (A * A - B * B) / (B * B);
+            let big_a =
+                1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+            let big_b = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+            let delta_sigma = big_b
+                * sin_sigma
+                * (cos_2sigma_m
+                    + big_b / 4.0
+                        * (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))
+                            - big_b / 6.0
+                                * cos_2sigma_m
+                                * (-3.0 + 4.0 * sin_sigma.powi(2))
+                                * (-3.0 + 4.0 * cos_2sigma_m.powi(2))));
+            return Some(B * big_a * (sigma - delta_sigma));
+        }
+    }
+
+    None
+}
+
+/// Point at `fraction` of the way along the great circle from `a` to `b`.
+/// Elevation and time, when both ends carry them, are interpolated linearly.
+pub fn great_circle_interpolate(a: &Coordinate, b: &Coordinate, fraction: f64) -> Coordinate {
+    let (lat1, lon1) = (a.lat.to_radians(), a.lon.to_radians());
+    let (lat2, lon2) = (b.lat.to_radians(), b.lon.to_radians());
+    let delta = haversine(a, b) / EARTH_RADIUS_M;
+
+    let (lat, lon) = if delta == 0.0 {
+        (a.lat, a.lon)
+    } else {
+        let wa = ((1.0 - fraction) * delta).sin() / delta.sin();
+        let wb = (fraction * delta).sin() / delta.sin();
+        let x = wa * lat1.cos() * lon1.cos() + wb * lat2.cos() * lon2.cos();
+        let y = wa * lat1.cos() * lon1.sin() + wb * lat2.cos() * lon2.sin();
+        let z = wa * lat1.sin() + wb * lat2.sin();
+        (
+            z.atan2((x * x + y * y).sqrt()).to_degrees(),
+            y.atan2(x).to_degrees(),
+        )
+    };
+
+    Coordinate {
+        lat,
+        lon,
+        ele: match (a.ele, b.ele) {
+            (Some(x), Some(y)) => Some(x + (y - x) * fraction),
+            _ => None,
+        },
+        time: match (a.time, b.time) {
+            (Some(x), Some(y)) => Some(x + ((y - x) as f64 * fraction).round() as i64),
+            _ => None,
+        },
+    }
+}
+
+/// Distance from the first point to every point along a line, in metres.
+pub fn cumulative(points: &[Coordinate]) -> Vec<f64> {
+    let mut distances = Vec::with_capacity(points.len());
+    let mut total = 0.0;
+
+    for (i, point) in points.iter().enumerate() {
+        if i > 0 {
+            total += haversine(&points[i - 1], point);
+        }
+        distances.push(total);
+    }
+
+    distances
+}
+
+/// Distance along a sequence of segments to every point, in metres. Each
+/// segment continues from where the previous one ended, so the gaps between
+/// segments are not counted.
+pub fn cumulative_segments<'a>(segments: impl IntoIterator<Item = &'a TrackSegment>) -> Vec<f64> {
+    let mut distances = Vec::new();
+    let mut offset = 0.0;
+    for segment in segments {
+        let segment_distances = cumulative(&segment.points);
+        distances.extend(segment_distances.iter().map(|d| offset + d));
+        offset += segment_distances.last().copied().unwrap_or(0.0);
+    }
+    distances
+}
+
+/// Total length of a line in metres.
+pub fn length(points: &[Coordinate]) -> f64 {
+    points
+        .windows(2)
+        .map(|pair| haversine(&pair[0], &pair[1]))
+        .sum()
+}
+
+/// Initial great-circle bearing from `a` to `b` in degrees clockwise from
+/// north, in the range `[0, 360)`.
+pub fn bearing(a: &Coordinate, b: &Coordinate) -> f64 {
+    let lat1 = a.lat.to_radians();
+    let lat2 = b.lat.to_radians();
+    let dlon = (b.lon - a.lon).to_radians();
+
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// The point `target` metres along a line, given its [`cumulative`]
+/// distances. Targets outside the line clamp to its ends. `points` must not
+/// be empty.
+pub fn point_at(points: &[Coordinate], distances: &[f64], target: f64) -> Coordinate {
+    if points.len() < 2 {
+        return points[0].clone();
+    }
+    let edge = distances
+        .partition_point(|&d| d <= target)
+        .clamp(1, points.len() - 1);
+
+    let (from, to) = (distances[edge - 1], distances[edge]);
+    let fraction = if to > from {
+        ((target - from) / (to - from)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    great_circle_interpolate(&points[edge - 1], &points[edge], fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_haversine_one_degree_latitude() {
+        let a = Coordinate::new(0.0, 0.0);
+        let b = Coordinate::new(1.0, 0.0);
+        assert!((haversine(&a, &b) - 111_195.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_haversine_same_point() {
+        let a = Coordinate::new(37.7749, -122.4194);
+        assert_eq!(haversine(&a, &a), 0.0);
+    }
+
+    #[test]
+    fn test_vincenty_flinders_peak_to_buninyong() {
+        let a = Coordinate::new(-37.951_033_416_7, 144.424_867_888_9);
+        let b = Coordinate::new(-37.652_821_138_9, 143.926_495_527_8);
+        assert!((vincenty(&a, &b).unwrap() - 54_972.271).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_vincenty_same_point() {
+        let a = Coordinate::new(37.7749, -122.4194);
+        assert_eq!(vincenty(&a, &a), Some(0.0));
+    }
+
+    #[test]
+    fn test_great_circle_midpoint() {
+        let a = Coordinate::new(0.0, 0.0);
+        let b = Coordinate::new(0.0, 90.0);
+        let mid = great_circle_interpolate(&a, &b, 0.5);
+        assert!(mid.lat.abs() < 1e-9);
+        assert!((mid.lon - 45.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_great_circle_follows_geodesic_not_parallel() {
+        // The great circle between two points on the same parallel bulges
+        // towards the pole.
+        let a = Coordinate::new(60.0, -30.0);
+        let b = Coordinate::new(60.0, 30.0);
+        let mid = great_circle_interpolate(&a, &b, 0.5);
+        assert!(mid.lat > 60.0);
+        assert!(mid.lon.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cumulative() {
+        let points = vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 1.0),
+            Coordinate::new(0.0, 2.0),
+        ];
+        let distances = cumulative(&points);
+        assert_eq!(distances.len(), 3);
+        assert_eq!(distances[0], 0.0);
+        assert!((distances[2] - 2.0 * distances[1]).abs() < 1e-6);
+        assert!((length(&points) - distances[2]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cumulative_segments_skips_gaps() {
+        let segments = vec![
+            TrackSegment::new(vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 1.0)]),
+            TrackSegment::new(vec![Coordinate::new(0.0, 5.0), Coordinate::new(0.0, 6.0)]),
+        ];
+        let distances = cumulative_segments(&segments);
+        assert_eq!(distances.len(), 4);
+        assert_eq!(distances[1], distances[2]);
+        assert!((distances[3] - 2.0 * distances[1]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_bearing_cardinal_directions() {
+        let origin = Coordinate::new(0.0, 0.0);
+        assert!((bearing(&origin, &Coordinate::new(1.0, 0.0)) - 0.0).abs() < 1e-9);
+        assert!((bearing(&origin, &Coordinate::new(0.0, 1.0)) - 90.0).abs() < 1e-9);
+        assert!((bearing(&origin, &Coordinate::new(-1.0, 0.0)) - 180.0).abs() < 1e-9);
+        assert!((bearing(&origin, &Coordinate::new(0.0, -1.0)) - 270.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_point_at() {
+        let points = vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 1.0),
+            Coordinate::new(0.0, 2.0),
+        ];
+        let distances = cumulative(&points);
+        let mid = point_at(&points, &distances, distances[1] * 1.5);
+        assert!((mid.lon - 1.5).abs() < 1e-9);
+        assert_eq!(point_at(&points, &distances, -5.0).lon, 0.0);
+        assert_eq!(point_at(&points, &distances, 1e9).lon, 2.0);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod distance;
+pub mod project;
+pub mod resample;
+pub mod simplify;
+pub mod snap;
@@ -0,0 +1,133 @@
+use super::distance::{cumulative, haversine, EARTH_RADIUS_M};
+use crate::types::Coordinate;
+
+/// The closest position on a line to some target coordinate.
+#[derive(Debug, Clone)]
+pub struct Projection {
+    /// Index of the line vertex that starts the closest edge.
+    pub index: usize,
+    /// Position along the closest edge, from 0.0 at `index` to 1.0 at `index + 1`.
+    pub fraction: f64,
+    /// The projected coordinate on the line.
+    pub point: Coordinate,
+    /// Distance from the start of the line to `point`, in metres.
+    pub distance_along: f64,
+    /// Distance from the target to `point`, in metres.
+    pub distance_off: f64,
+}
+
+/// Finds the position on `points` closest to `target`.
+///
+/// Each edge is projected in a local equirectangular frame, which is accurate
+/// for the short edges found in route geometry.
+pub fn project(points: &[Coordinate], target: &Coordinate) -> Option<Projection> {
+    match points.len() {
+        0 => return None,
+        1 => {
+            return Some(Projection {
+                index: 0,
+                fraction: 0.0,
+                point: points[0].clone(),
+                distance_along: 0.0,
+                distance_off: haversine(&points[0], target),
+            })
+        }
+        _ => {}
+    }
+
+    let distances = cumulative(points);
+    let mut best: Option<Projection> = None;
+
+    for (i, pair) in points.windows(2).enumerate() {
+        let fraction = edge_fraction(&pair[0], &pair[1], target);
+        let point = interpolate(&pair[0], &pair[1], fraction);
+        let distance_off = haversine(&point, target);
+
+        if best.as_ref().is_none_or(|b| distance_off < b.distance_off) {
+            let edge_length = distances[i + 1] - distances[i];
+            best = Some(Projection {
+                index: i,
+                fraction,
+                point,
+                distance_along: distances[i] + edge_length * fraction,
+                distance_off,
+            });
+        }
+    }
+
+    best
+}
+
+/// Linear interpolation between two coordinates, including elevation and time.
+pub fn interpolate(a: &Coordinate, b: &Coordinate, fraction: f64) -> Coordinate {
+    let ele = match (a.ele, b.ele) {
+        (Some(x), Some(y)) => Some(x + (y - x) * fraction),
+        (x, y) => x.or(y),
+    };
+    let time = match (a.time, b.time) {
+        (Some(x), Some(y)) => Some(x + ((y - x) as f64 * fraction).round() as i64),
+        _ => None,
+    };
+
+    Coordinate {
+        lat: a.lat + (b.lat - a.lat) * fraction,
+        lon: a.lon + (b.lon - a.lon) * fraction,
+        ele,
+        time,
+    }
+}
+
+fn edge_fraction(a: &Coordinate, b: &Coordinate, target: &Coordinate) -> f64 {
+    let scale = a.lat.to_radians().cos();
+    let bx = (b.lon - a.lon).to_radians() * scale * EARTH_RADIUS_M;
+    let by = (b.lat - a.lat).to_radians() * EARTH_RADIUS_M;
+    let tx = (target.lon - a.lon).to_radians() * scale * EARTH_RADIUS_M;
+    let ty = (target.lat - a.lat).to_radians() * EARTH_RADIUS_M;
+
+    let length_sq = bx * bx + by * by;
+    if length_sq == 0.0 {
+        return 0.0;
+    }
+
+    ((tx * bx + ty * by) / length_sq).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_onto_middle_of_edge() {
+        let points = vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.02)];
+        let projection = project(&points, &Coordinate::new(0.001, 0.01)).unwrap();
+        assert_eq!(projection.index, 0);
+        assert!((projection.fraction - 0.5).abs() < 1e-6);
+        assert!(projection.point.lat.abs() < 1e-9);
+        assert!((projection.distance_off - 111.2).abs() < 0.5);
+        assert!((projection.distance_along - haversine(&points[0], &points[1]) / 2.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_project_picks_closest_edge() {
+        let points = vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 0.01),
+            Coordinate::new(0.01, 0.01),
+        ];
+        let projection = project(&points, &Coordinate::new(0.005, 0.011)).unwrap();
+        assert_eq!(projection.index, 1);
+    }
+
+    #[test]
+    fn test_project_clamps_to_endpoints() {
+        let points = vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01)];
+        let projection = project(&points, &Coordinate::new(0.0, -0.01)).unwrap();
+        assert_eq!(projection.fraction, 0.0);
+        assert_eq!(projection.distance_along, 0.0);
+    }
+
+    #[test]
+    fn test_project_empty_line() {
+        assert!(project(&[], &Coordinate::new(0.0, 0.0)).is_none());
+    }
+}
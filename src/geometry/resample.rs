@@ -0,0 +1,122 @@
+use super::distance::{cumulative, great_circle_interpolate};
+use crate::types::{Route, TrackSegment};
+
+/// Smallest spacing used for resampling, in metres. Closer spacings are
+/// raised to this so the number of points stays bounded.
+pub const MIN_SPACING: f64 = 1.0;
+
+/// Resamples a segment to points spaced `spacing` metres apart along the
+/// great circle, keeping the original end point. Elevation and time are
+/// interpolated when the surrounding points carry them.
+pub fn resample(segment: &TrackSegment, spacing: f64) -> TrackSegment {
+    let points = &segment.points;
+    if points.len() < 2 || spacing <= 0.0 || !spacing.is_finite() {
+        return segment.clone();
+    }
+    let spacing = spacing.max(MIN_SPACING);
+
+    let distances = cumulative(points);
+    let total = distances[distances.len() - 1];
+    let mut resampled = vec![points[0].clone()];
+    let mut edge = 0;
+    let mut target = spacing;
+
+    while target < total {
+        while distances[edge + 1] < target {
+            edge += 1;
+        }
+
+        let edge_length = distances[edge + 1] - distances[edge];
+        let fraction = if edge_length > 0.0 {
+            (target - distances[edge]) / edge_length
+        } else {
+            0.0
+        };
+        resampled.push(great_circle_interpolate(
+            &points[edge],
+            &points[edge + 1],
+            fraction,
+        ));
+        target += spacing;
+    }
+
+    resampled.push(points[points.len() - 1].clone());
+    TrackSegment::new(resampled)
+}
+
+pub fn resample_route(route: &Route, spacing: f64) -> Route {
+    let mut resampled = route.clone();
+    for track in &mut resampled.tracks {
+        for segment in &mut track.segments {
+            *segment = resample(segment, spacing);
+        }
+    }
+    resampled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::distance::haversine;
+    use crate::types::Coordinate;
+
+    #[test]
+    fn test_resample_even_spacing() {
+        let segment = TrackSegment::new(vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 0.0001),
+            Coordinate::new(0.0, 0.01),
+        ]);
+        let resampled = resample(&segment, 100.0);
+
+        for pair in resampled.points.windows(2).take(resampled.points.len() - 2) {
+            assert!((haversine(&pair[0], &pair[1]) - 100.0).abs() < 0.01);
+        }
+        let last = resampled.points.last().unwrap();
+        assert_eq!(last.lon, 0.01);
+        assert_eq!(resampled.points.len(), 13);
+    }
+
+    #[test]
+    fn test_resample_interpolates_elevation_and_time() {
+        let mut start = Coordinate::with_elevation(0.0, 0.0, 100.0);
+        start.time = Some(0);
+        let mut end = Coordinate::with_elevation(0.0, 0.002, 200.0);
+        end.time = Some(60_000);
+
+        let segment = TrackSegment::new(vec![start, end]);
+        let total = haversine(&segment.points[0], &segment.points[1]);
+        let resampled = resample(&segment, total / 4.0);
+
+        assert_eq!(resampled.points.len(), 5);
+        let middle = &resampled.points[2];
+        assert!((middle.ele.unwrap() - 150.0).abs() < 1e-6);
+        assert_eq!(middle.time, Some(30_000));
+    }
+
+    #[test]
+    fn test_resample_leaves_missing_values_empty() {
+        let segment = TrackSegment::new(vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::with_elevation(0.0, 0.01, 10.0),
+        ]);
+        let resampled = resample(&segment, 100.0);
+        assert!(resampled.points[1].ele.is_none());
+        assert!(resampled.points[1].time.is_none());
+    }
+
+    #[test]
+    fn test_resample_tiny_spacing_is_clamped() {
+        let segment = TrackSegment::new(vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 1.0)]);
+        let total = haversine(&segment.points[0], &segment.points[1]);
+        let resampled = resample(&segment, 1e-6);
+        assert_eq!(resampled.points.len(), total.ceil() as usize + 1);
+    }
+
+    #[test]
+    fn test_resample_invalid_spacing() {
+        let segment = TrackSegment::new(vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 1.0)]);
+        assert_eq!(resample(&segment, 0.0).points.len(), 2);
+        assert_eq!(resample(&segment, f64::NAN).points.len(), 2);
+    }
+}
@@ -0,0 +1,389 @@
+use super::distance::EARTH_RADIUS_M;
+use super::project::project;
+use crate::types::{Route, TrackSegment};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    DouglasPeucker,
+    VisvalingamWhyatt,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimplifyOptions {
+    pub algorithm: Algorithm,
+    /// Maximum deviation in metres. For Visvalingam-Whyatt, points whose
+    /// effective triangle area is below `tolerance²` square metres are removed.
+    pub tolerance: f64,
+    /// Upper bound on the number of points kept per segment.
+    pub target_points: Option<usize>,
+    /// Keep the track vertex nearest to each waypoint.
+    pub preserve_waypoints: bool,
+}
+
+impl Default for SimplifyOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::DouglasPeucker,
+            tolerance: 5.0,
+            target_points: None,
+            preserve_waypoints: true,
+        }
+    }
+}
+
+/// Simplifies a segment, always keeping its endpoints and the indices in `keep`.
+pub fn simplify(segment: &TrackSegment, options: &SimplifyOptions, keep: &[usize]) -> TrackSegment {
+    let points = &segment.points;
+    if points.len() <= 2 {
+        return segment.clone();
+    }
+
+    let planar = to_planar(segment);
+    let (ranking, threshold) = match options.algorithm {
+        Algorithm::DouglasPeucker => (douglas_peucker(&planar), options.tolerance),
+        Algorithm::VisvalingamWhyatt => (
+            visvalingam_whyatt(&planar),
+            options.tolerance * options.tolerance,
+        ),
+    };
+    let importance: Vec<f64> = ranking.iter().map(|r| r.0).collect();
+    let keep: HashSet<usize> = keep.iter().copied().collect();
+
+    let mut ranked: Vec<usize> = (0..points.len())
+        .filter(|&i| importance[i] > threshold || keep.contains(&i))
+        .collect();
+
+    if let Some(target) = options.target_points {
+        if ranked.len() > target {
+            // Endpoints first, then forced indices, then by importance.
+            let last = points.len() - 1;
+            let priority = |i: usize| match i {
+                0 => 2,
+                i if i == last => 2,
+                i => keep.contains(&i) as u8,
+            };
+            ranked.sort_by(|&a, &b| {
+                priority(b)
+                    .cmp(&priority(a))
+                    .then(ranking[b].0.total_cmp(&ranking[a].0))
+                    .then(ranking[b].1.cmp(&ranking[a].1))
+            });
+            ranked.truncate(target.max(2));
+            ranked.sort_unstable();
+        }
+    }
+
+    TrackSegment::new(ranked.into_iter().map(|i| points[i].clone()).collect())
+}
+
+/// Simplifies every segment of a route. When waypoints are preserved, each
+/// waypoint pins the nearest vertex of the segment it lies closest to.
+pub fn simplify_route(route: &Route, options: &SimplifyOptions) -> Route {
+    let mut keep: Vec<Vec<Vec<usize>>> = route
+        .tracks
+        .iter()
+        .map(|track| vec![Vec::new(); track.segments.len()])
+        .collect();
+
+    if options.preserve_waypoints {
+        for waypoint in &route.waypoints {
+            let nearest = route
+                .tracks
+                .iter()
+                .enumerate()
+                .flat_map(|(t, track)| {
+                    track
+                        .segments
+                        .iter()
+                        .enumerate()
+                        .filter_map(move |(s, segment)| {
+                            project(&segment.points, &waypoint.coord).map(|p| (t, s, p))
+                        })
+                })
+                .min_by(|a, b| a.2.distance_off.total_cmp(&b.2.distance_off));
+
+            if let Some((t, s, projection)) = nearest {
+                let vertex = if projection.fraction < 0.5 {
+                    projection.index
+                } else {
+                    projection.index + 1
+                };
+                keep[t][s].push(vertex);
+            }
+        }
+    }
+
+    let mut simplified = route.clone();
+    for (t, track) in simplified.tracks.iter_mut().enumerate() {
+        for (s, segment) in track.segments.iter_mut().enumerate() {
+            *segment = simplify(segment, options, &keep[t][s]);
+        }
+    }
+    simplified
+}
+
+/// Projects points onto a local plane in metres, centred on the segment's
+/// mean latitude.
+fn to_planar(segment: &TrackSegment) -> Vec<(f64, f64)> {
+    let points = &segment.points;
+    let mean_lat = points.iter().map(|p| p.lat).sum::<f64>() / points.len() as f64;
+    let scale = mean_lat.to_radians().cos();
+
+    points
+        .iter()
+        .map(|p| {
+            (
+                p.lon.to_radians() * scale * EARTH_RADIUS_M,
+                p.lat.to_radians() * EARTH_RADIUS_M,
+            )
+        })
+        .collect()
+}
+
+/// Importance of each point as `(value, tiebreak)`, where a larger tiebreak
+/// wins between equal values.
+type Ranking = Vec<(f64, usize)>;
+
+/// Distance at which Douglas-Peucker would keep each point. A point's value
+/// never exceeds that of the split which introduced it, so thresholding the
+/// result is equivalent to running the algorithm with that tolerance. Ties
+/// favour the shallower split.
+fn douglas_peucker(points: &[(f64, f64)]) -> Ranking {
+    let last = points.len() - 1;
+    let mut ranking = vec![(0.0, 0); points.len()];
+    ranking[0] = (f64::INFINITY, usize::MAX);
+    ranking[last] = (f64::INFINITY, usize::MAX);
+
+    let mut stack = vec![(0, last, f64::INFINITY, 0)];
+    while let Some((start, end, parent, depth)) = stack.pop() {
+        if end <= start + 1 {
+            continue;
+        }
+
+        let (index, distance) = (start + 1..end)
+            .map(|i| {
+                (
+                    i,
+                    perpendicular_distance(points[i], points[start], points[end]),
+                )
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+
+        let value = distance.min(parent);
+        ranking[index] = (value, usize::MAX - depth);
+        stack.push((start, index, value, depth + 1));
+        stack.push((index, end, value, depth + 1));
+    }
+
+    ranking
+}
+
+fn perpendicular_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    if length_sq == 0.0 {
+        return ((p.0 - a.0).powi(2) + (p.1 - a.1).powi(2)).sqrt();
+    }
+
+    let t = (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_sq).clamp(0.0, 1.0);
+    let (x, y) = (a.0 + t * dx, a.1 + t * dy);
+    ((p.0 - x).powi(2) + (p.1 - y).powi(2)).sqrt()
+}
+
+#[derive(PartialEq)]
+struct Candidate {
+    area: f64,
+    index: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    // Reversed so the binary heap pops the smallest area first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .area
+            .total_cmp(&self.area)
+            .then(other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Effective area of each point at the moment Visvalingam-Whyatt removes it.
+/// Ties favour the point removed later.
+fn visvalingam_whyatt(points: &[(f64, f64)]) -> Ranking {
+    let len = points.len();
+    let mut ranking = vec![(f64::INFINITY, usize::MAX); len];
+    let mut areas = vec![f64::INFINITY; len];
+    let mut prev: Vec<usize> = (0..len).map(|i| i.saturating_sub(1)).collect();
+    let mut next: Vec<usize> = (0..len).map(|i| (i + 1).min(len - 1)).collect();
+    let mut heap = BinaryHeap::new();
+
+    for i in 1..len - 1 {
+        areas[i] = triangle_area(points[i - 1], points[i], points[i + 1]);
+        heap.push(Candidate {
+            area: areas[i],
+            index: i,
+        });
+    }
+
+    let mut max_area: f64 = 0.0;
+    let mut removed = 0;
+    while let Some(Candidate { area, index }) = heap.pop() {
+        // Skip stale heap entries left behind by neighbour updates.
+        if area != areas[index] || ranking[index].0.is_finite() {
+            continue;
+        }
+
+        max_area = max_area.max(area);
+        ranking[index] = (max_area, removed);
+        removed += 1;
+
+        let (p, n) = (prev[index], next[index]);
+        next[p] = n;
+        prev[n] = p;
+
+        for neighbour in [p, n] {
+            if neighbour == 0 || neighbour == len - 1 {
+                continue;
+            }
+            areas[neighbour] = triangle_area(
+                points[prev[neighbour]],
+                points[neighbour],
+                points[next[neighbour]],
+            );
+            heap.push(Candidate {
+                area: areas[neighbour],
+                index: neighbour,
+            });
+        }
+    }
+
+    ranking
+}
+
+fn triangle_area(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    ((a.0 * (b.1 - c.1) + b.0 * (c.1 - a.1) + c.0 * (a.1 - b.1)) / 2.0).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Coordinate, Track, Waypoint};
+
+    // A straight east-west line with a 100 m bump in the middle.
+    fn bumpy_segment() -> TrackSegment {
+        let mut points: Vec<Coordinate> = (0..=20)
+            .map(|i| Coordinate::new(0.0, i as f64 * 0.001))
+            .collect();
+        points[10].lat = 0.0009;
+        TrackSegment::new(points)
+    }
+
+    #[test]
+    fn test_douglas_peucker_keeps_significant_points() {
+        let simplified = simplify(&bumpy_segment(), &SimplifyOptions::default(), &[]);
+        assert_eq!(simplified.points.len(), 5);
+        assert!((simplified.points[2].lat - 0.0009).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_douglas_peucker_large_tolerance() {
+        let options = SimplifyOptions {
+            tolerance: 200.0,
+            ..SimplifyOptions::default()
+        };
+        let simplified = simplify(&bumpy_segment(), &options, &[]);
+        assert_eq!(simplified.points.len(), 2);
+    }
+
+    #[test]
+    fn test_visvalingam_whyatt_keeps_significant_points() {
+        let options = SimplifyOptions {
+            algorithm: Algorithm::VisvalingamWhyatt,
+            tolerance: 50.0,
+            ..SimplifyOptions::default()
+        };
+        let simplified = simplify(&bumpy_segment(), &options, &[]);
+        assert!(simplified.points.len() < 21);
+        assert!(simplified.points.iter().any(|p| p.lat > 0.0));
+    }
+
+    #[test]
+    fn test_target_points() {
+        for algorithm in [Algorithm::DouglasPeucker, Algorithm::VisvalingamWhyatt] {
+            let options = SimplifyOptions {
+                algorithm,
+                tolerance: 0.0,
+                target_points: Some(3),
+                ..SimplifyOptions::default()
+            };
+            let simplified = simplify(&bumpy_segment(), &options, &[]);
+            assert_eq!(simplified.points.len(), 3);
+            assert_eq!(simplified.points[0].lon, 0.0);
+            assert!((simplified.points[2].lon - 0.02).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_target_points_keeps_most_important() {
+        let options = SimplifyOptions {
+            tolerance: 0.0,
+            target_points: Some(3),
+            ..SimplifyOptions::default()
+        };
+        let simplified = simplify(&bumpy_segment(), &options, &[]);
+        assert!((simplified.points[1].lat - 0.0009).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_keeps_forced_indices() {
+        let simplified = simplify(&bumpy_segment(), &SimplifyOptions::default(), &[3]);
+        assert!(simplified
+            .points
+            .iter()
+            .any(|p| (p.lon - 0.003).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_target_points_keeps_endpoints_before_forced_indices() {
+        let options = SimplifyOptions {
+            target_points: Some(3),
+            ..SimplifyOptions::default()
+        };
+        let simplified = simplify(&bumpy_segment(), &options, &[3, 5, 7]);
+        let lons: Vec<f64> = simplified.points.iter().map(|p| p.lon).collect();
+        assert_eq!(lons.len(), 3);
+        assert_eq!(lons[0], 0.0);
+        assert!((lons[2] - 0.02).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_simplify_route_preserves_waypoint_vertices() {
+        let mut route = Route::new();
+        route.add_track(Track::new(vec![bumpy_segment()]));
+        route.add_waypoint(Waypoint::new(Coordinate::new(0.0001, 0.0171)));
+
+        let simplified = simplify_route(&route, &SimplifyOptions::default());
+        let points = &simplified.tracks[0].segments[0].points;
+        assert!(points.iter().any(|p| (p.lon - 0.017).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_short_segment_unchanged() {
+        let segment = TrackSegment::new(vec![Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0)]);
+        let simplified = simplify(&segment, &SimplifyOptions::default(), &[]);
+        assert_eq!(simplified.points.len(), 2);
+    }
+}
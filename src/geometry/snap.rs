@@ -0,0 +1,274 @@
+use super::distance::{haversine, length};
+use super::project::{interpolate, project};
+use crate::types::{Coordinate, Route};
+use serde::{Deserialize, Serialize};
+
+/// Snapped points closer than this to an existing vertex reuse the vertex.
+const VERTEX_TOLERANCE_M: f64 = 0.5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapOptions {
+    /// Waypoints further than this many metres from every track are treated
+    /// as off-route and never inserted into the geometry.
+    pub max_distance: Option<f64>,
+    /// Sort waypoints by their distance along the route.
+    pub reorder: bool,
+    /// Insert each on-route waypoint's snapped position as a track point.
+    pub insert_points: bool,
+}
+
+impl Default for SnapOptions {
+    fn default() -> Self {
+        Self {
+            max_distance: None,
+            reorder: true,
+            insert_points: false,
+        }
+    }
+}
+
+/// Where a waypoint lies relative to the route's tracks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaypointPosition {
+    /// Index of the waypoint in the route passed in, before any reordering.
+    pub waypoint_index: usize,
+    pub track_index: usize,
+    pub segment_index: usize,
+    /// Index of the segment point that starts the closest edge.
+    pub point_index: usize,
+    /// Position along that edge, from 0.0 to 1.0.
+    pub fraction: f64,
+    /// Distance along the whole route, summing the lengths of the segments
+    /// before this one, in metres.
+    pub distance_along: f64,
+    /// Distance from the waypoint to the track, in metres.
+    pub distance_off: f64,
+    pub off_route: bool,
+}
+
+/// Finds the closest track position for each waypoint, in waypoint order.
+/// Waypoints are `None` when the route has no track points.
+pub fn locate_waypoints(route: &Route, max_distance: Option<f64>) -> Vec<Option<WaypointPosition>> {
+    route
+        .waypoints
+        .iter()
+        .enumerate()
+        .map(|(i, waypoint)| locate(route, i, &waypoint.coord, max_distance))
+        .collect()
+}
+
+fn locate(
+    route: &Route,
+    waypoint_index: usize,
+    target: &Coordinate,
+    max_distance: Option<f64>,
+) -> Option<WaypointPosition> {
+    let mut offset = 0.0;
+    let mut best: Option<WaypointPosition> = None;
+
+    for (track_index, track) in route.tracks.iter().enumerate() {
+        for (segment_index, segment) in track.segments.iter().enumerate() {
+            if let Some(p) = project(&segment.points, target) {
+                if best
+                    .as_ref()
+                    .is_none_or(|b| p.distance_off < b.distance_off)
+                {
+                    best = Some(WaypointPosition {
+                        waypoint_index,
+                        track_index,
+                        segment_index,
+                        point_index: p.index,
+                        fraction: p.fraction,
+                        distance_along: offset + p.distance_along,
+                        distance_off: p.distance_off,
+                        off_route: max_distance.is_some_and(|max| p.distance_off > max),
+                    });
+                }
+            }
+            offset += length(&segment.points);
+        }
+    }
+
+    best
+}
+
+/// Locates every waypoint on the tracks, optionally inserting the snapped
+/// points into the track geometry and sorting the waypoints into route
+/// order. Returns the new route with one position per located waypoint, in
+/// the same order as its waypoints.
+pub fn snap_waypoints(route: &Route, options: &SnapOptions) -> (Route, Vec<WaypointPosition>) {
+    let mut snapped = route.clone();
+    let mut positions = locate_waypoints(route, options.max_distance);
+
+    if options.insert_points {
+        let mut inserts: Vec<&WaypointPosition> = positions
+            .iter()
+            .flatten()
+            .filter(|p| !p.off_route)
+            .collect();
+        // Insert from the back so earlier indices stay valid.
+        inserts.sort_by(|a, b| {
+            (b.track_index, b.segment_index, b.point_index)
+                .cmp(&(a.track_index, a.segment_index, a.point_index))
+                .then(b.fraction.total_cmp(&a.fraction))
+        });
+
+        for position in inserts {
+            let points =
+                &mut snapped.tracks[position.track_index].segments[position.segment_index].points;
+            let Some(next) = points.get(position.point_index + 1) else {
+                continue;
+            };
+            let point = interpolate(&points[position.point_index], next, position.fraction);
+            let near_vertex = haversine(&point, &points[position.point_index]) < VERTEX_TOLERANCE_M
+                || haversine(&point, next) < VERTEX_TOLERANCE_M;
+            if !near_vertex {
+                points.insert(position.point_index + 1, point);
+            }
+        }
+
+        positions = locate_waypoints(&snapped, options.max_distance);
+    }
+
+    let mut order: Vec<usize> = (0..snapped.waypoints.len()).collect();
+    if options.reorder {
+        // Waypoints that could not be located keep their relative order at
+        // the end.
+        order.sort_by(|&a, &b| {
+            let along = |i: usize| positions[i].as_ref().map(|p| p.distance_along);
+            match (along(a), along(b)) {
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            }
+        });
+    }
+
+    snapped.waypoints = order.iter().map(|&i| route.waypoints[i].clone()).collect();
+    let positions = order.iter().filter_map(|&i| positions[i].clone()).collect();
+    (snapped, positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Track, TrackSegment, Waypoint};
+
+    fn route() -> Route {
+        let mut route = Route::new();
+        route.add_track(Track::new(vec![
+            TrackSegment::new(vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01)]),
+            TrackSegment::new(vec![Coordinate::new(0.0, 0.02), Coordinate::new(0.0, 0.03)]),
+        ]));
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0001, 0.025),
+            "Late".to_string(),
+        ));
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.05, 0.008),
+            "Far".to_string(),
+        ));
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(-0.0001, 0.005),
+            "Early".to_string(),
+        ));
+        route
+    }
+
+    fn names(route: &Route) -> Vec<&str> {
+        route
+            .waypoints
+            .iter()
+            .map(|w| w.name.as_deref().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_locate_across_segments() {
+        let positions = locate_waypoints(&route(), None);
+        let late = positions[0].as_ref().unwrap();
+        let segment = length(&[Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01)]);
+
+        assert_eq!((late.track_index, late.segment_index), (0, 1));
+        assert!((late.fraction - 0.5).abs() < 1e-3);
+        assert!((late.distance_along - segment * 1.5).abs() < 1.0);
+        assert!((late.distance_off - 11.1).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_reorder_by_distance_along() {
+        let (snapped, positions) = snap_waypoints(&route(), &SnapOptions::default());
+        assert_eq!(names(&snapped), vec!["Early", "Far", "Late"]);
+        assert_eq!(
+            positions
+                .iter()
+                .map(|p| p.waypoint_index)
+                .collect::<Vec<_>>(),
+            vec![2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_off_route() {
+        let options = SnapOptions {
+            max_distance: Some(100.0),
+            ..Default::default()
+        };
+        let (_, positions) = snap_waypoints(&route(), &options);
+        assert!(
+            positions
+                .iter()
+                .find(|p| p.waypoint_index == 1)
+                .unwrap()
+                .off_route
+        );
+        assert!(
+            !positions
+                .iter()
+                .find(|p| p.waypoint_index == 0)
+                .unwrap()
+                .off_route
+        );
+    }
+
+    #[test]
+    fn test_insert_snapped_points() {
+        let options = SnapOptions {
+            max_distance: Some(100.0),
+            insert_points: true,
+            ..Default::default()
+        };
+        let (snapped, positions) = snap_waypoints(&route(), &options);
+        let segments = &snapped.tracks[0].segments;
+
+        assert_eq!(segments[0].points.len(), 3);
+        assert_eq!(segments[1].points.len(), 3);
+        assert!((segments[1].points[1].lon - 0.025).abs() < 1e-9);
+        let late = positions.iter().find(|p| p.waypoint_index == 0).unwrap();
+        let segment = length(&[Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01)]);
+        assert!((late.distance_along - segment * 1.5).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_insert_skips_existing_vertex() {
+        let mut route = route();
+        route.waypoints = vec![Waypoint::new(Coordinate::new(0.0001, 0.01))];
+        let options = SnapOptions {
+            insert_points: true,
+            ..Default::default()
+        };
+        let (snapped, _) = snap_waypoints(&route, &options);
+        assert_eq!(snapped.tracks[0].segments[0].points.len(), 2);
+    }
+
+    #[test]
+    fn test_no_tracks() {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::new(Coordinate::new(0.0, 0.0)));
+        let (snapped, positions) = snap_waypoints(&route, &SnapOptions::default());
+        assert_eq!(snapped.waypoints.len(), 1);
+        assert!(positions.is_empty());
+    }
+}
@@ -1,4 +1,3 @@
 mod writer;
 
-pub use writer::write;
-
+pub use writer::{write, GpxError};
@@ -1,3 +1,5 @@
+use crate::stats;
+use crate::time::format_iso8601;
 use crate::types::Route;
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::Writer;
@@ -57,12 +59,30 @@ pub fn write(route: &Route) -> Result<String, GpxError> {
             writer.write_event(Event::End(BytesEnd::new("ele")))?;
         }
 
+        if let Some(time) = waypoint.coord.time {
+            writer.write_event(Event::Start(BytesStart::new("time")))?;
+            writer.write_event(Event::Text(BytesText::new(&format_iso8601(time))))?;
+            writer.write_event(Event::End(BytesEnd::new("time")))?;
+        }
+
         if let Some(ref name) = waypoint.name {
             writer.write_event(Event::Start(BytesStart::new("name")))?;
             writer.write_event(Event::Text(BytesText::new(name)))?;
             writer.write_event(Event::End(BytesEnd::new("name")))?;
         }
 
+        if let Some(ref symbol) = waypoint.symbol {
+            writer.write_event(Event::Start(BytesStart::new("sym")))?;
+            writer.write_event(Event::Text(BytesText::new(symbol)))?;
+            writer.write_event(Event::End(BytesEnd::new("sym")))?;
+        }
+
+        if let Some(ref kind) = waypoint.kind {
+            writer.write_event(Event::Start(BytesStart::new("type")))?;
+            writer.write_event(Event::Text(BytesText::new(kind)))?;
+            writer.write_event(Event::End(BytesEnd::new("type")))?;
+        }
+
         writer.write_event(Event::End(BytesEnd::new("wpt")))?;
     }
 
@@ -90,6 +110,12 @@ pub fn write(route: &Route) -> Result<String, GpxError> {
                     writer.write_event(Event::End(BytesEnd::new("ele")))?;
                 }
 
+                if let Some(time) = point.time {
+                    writer.write_event(Event::Start(BytesStart::new("time")))?;
+                    writer.write_event(Event::Text(BytesText::new(&format_iso8601(time))))?;
+                    writer.write_event(Event::End(BytesEnd::new("time")))?;
+                }
+
                 writer.write_event(Event::End(BytesEnd::new("trkpt")))?;
             }
 
@@ -117,6 +143,15 @@ fn write_metadata<W: std::io::Write>(
         writer.write_event(Event::End(BytesEnd::new("name")))?;
     }
 
+    if let Some(bounds) = stats::bounds(route) {
+        let mut element = BytesStart::new("bounds");
+        element.push_attribute(("minlat", bounds.min_lat.to_string().as_str()));
+        element.push_attribute(("minlon", bounds.min_lon.to_string().as_str()));
+        element.push_attribute(("maxlat", bounds.max_lat.to_string().as_str()));
+        element.push_attribute(("maxlon", bounds.max_lon.to_string().as_str()));
+        writer.write_event(Event::Empty(element))?;
+    }
+
     writer.write_event(Event::End(BytesEnd::new("metadata")))?;
 
     Ok(())
@@ -219,5 +254,40 @@ mod tests {
         let trkseg_count = gpx.matches("<trkseg>").count();
         assert_eq!(trkseg_count, 2);
     }
-}
 
+    #[test]
+    fn test_write_metadata_bounds() {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::new(Coordinate::new(37.7749, -122.4194)));
+        let segment = TrackSegment::new(vec![Coordinate::new(37.7835, -122.4089)]);
+        route.add_track(Track::new(vec![segment]));
+
+        let gpx = write(&route).unwrap();
+        assert!(gpx.contains(
+            "<bounds minlat=\"37.7749\" minlon=\"-122.4194\" maxlat=\"37.7835\" maxlon=\"-122.4089\"/>"
+        ));
+    }
+
+    #[test]
+    fn test_write_point_time() {
+        let mut route = Route::new();
+        let mut point = Coordinate::with_elevation(37.7749, -122.4194, 16.0);
+        point.time = Some(1_714_552_200_000);
+        route.add_track(Track::new(vec![TrackSegment::new(vec![point])]));
+
+        let gpx = write(&route).unwrap();
+        assert!(gpx.contains("<ele>16</ele><time>2024-05-01T08:30:00Z</time>"));
+    }
+
+    #[test]
+    fn test_write_waypoint_symbol_and_type() {
+        let mut route = Route::new();
+        let mut waypoint = Waypoint::with_name(Coordinate::new(1.0, 2.0), "Left".to_string());
+        waypoint.symbol = Some("Left".to_string());
+        waypoint.kind = Some("cue".to_string());
+        route.add_waypoint(waypoint);
+
+        let gpx = write(&route).unwrap();
+        assert!(gpx.contains("<name>Left</name><sym>Left</sym><type>cue</type>"));
+    }
+}
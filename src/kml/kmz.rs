@@ -0,0 +1,119 @@
+//! Minimal ZIP packaging for KMZ archives. Entries are stored without
+//! compression, which every KMZ reader accepts and keeps the WASM build free
+//! of a deflate dependency.
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const VERSION: u16 = 20;
+// 1980-01-01, the earliest date a ZIP entry can carry.
+const DOS_DATE: u16 = 0x0021;
+
+pub fn package(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut central = Vec::new();
+
+    for (name, data) in entries {
+        let offset = archive.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+
+        push_u32(&mut archive, LOCAL_HEADER_SIGNATURE);
+        push_u16(&mut archive, VERSION);
+        push_u16(&mut archive, 0);
+        push_u16(&mut archive, 0);
+        push_u16(&mut archive, 0);
+        push_u16(&mut archive, DOS_DATE);
+        push_u32(&mut archive, crc);
+        push_u32(&mut archive, size);
+        push_u32(&mut archive, size);
+        push_u16(&mut archive, name.len() as u16);
+        push_u16(&mut archive, 0);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        push_u32(&mut central, CENTRAL_HEADER_SIGNATURE);
+        push_u16(&mut central, VERSION);
+        push_u16(&mut central, VERSION);
+        push_u16(&mut central, 0);
+        push_u16(&mut central, 0);
+        push_u16(&mut central, 0);
+        push_u16(&mut central, DOS_DATE);
+        push_u32(&mut central, crc);
+        push_u32(&mut central, size);
+        push_u32(&mut central, size);
+        push_u16(&mut central, name.len() as u16);
+        push_u16(&mut central, 0);
+        push_u16(&mut central, 0);
+        push_u16(&mut central, 0);
+        push_u16(&mut central, 0);
+        push_u32(&mut central, 0);
+        push_u32(&mut central, offset);
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset = archive.len() as u32;
+    let central_size = central.len() as u32;
+    archive.extend_from_slice(&central);
+
+    push_u32(&mut archive, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+    push_u16(&mut archive, 0);
+    push_u16(&mut archive, 0);
+    push_u16(&mut archive, entries.len() as u16);
+    push_u16(&mut archive, entries.len() as u16);
+    push_u32(&mut archive, central_size);
+    push_u32(&mut archive, central_offset);
+    push_u16(&mut archive, 0);
+
+    archive
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn push_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_package_layout() {
+        let archive = package(&[("doc.kml", b"<kml/>")]);
+        assert_eq!(&archive[..4], &LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        assert_eq!(&archive[30..37], b"doc.kml");
+        assert_eq!(&archive[37..43], b"<kml/>");
+
+        let eocd = &archive[archive.len() - 22..];
+        assert_eq!(
+            &eocd[..4],
+            &END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes()
+        );
+        assert_eq!(u16::from_le_bytes([eocd[10], eocd[11]]), 1);
+        let central_offset = u32::from_le_bytes([eocd[16], eocd[17], eocd[18], eocd[19]]) as usize;
+        assert_eq!(
+            &archive[central_offset..central_offset + 4],
+            &CENTRAL_HEADER_SIGNATURE.to_le_bytes()
+        );
+    }
+}
@@ -0,0 +1,4 @@
+mod kmz;
+mod writer;
+
+pub use writer::{write, write_kmz, KmlError, KmlOptions};
@@ -0,0 +1,296 @@
+use super::kmz;
+use crate::types::{Coordinate, Route, Track, Waypoint};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::Writer;
+use std::io::Cursor;
+use thiserror::Error;
+
+const TRACK_STYLE_ID: &str = "track";
+const WAYPOINT_STYLE_ID: &str = "waypoint";
+
+#[derive(Error, Debug)]
+pub enum KmlError {
+    #[error("XML writing error: {0}")]
+    XmlError(String),
+    #[error("IO error: {0}")]
+    IoError(String),
+}
+
+impl From<quick_xml::Error> for KmlError {
+    fn from(e: quick_xml::Error) -> Self {
+        KmlError::XmlError(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for KmlError {
+    fn from(e: std::io::Error) -> Self {
+        KmlError::IoError(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KmlOptions {
+    pub description: Option<String>,
+    /// Line colour in KML's `aabbggrr` hex notation.
+    pub line_color: String,
+    pub line_width: f64,
+}
+
+impl Default for KmlOptions {
+    fn default() -> Self {
+        Self {
+            description: None,
+            line_color: "ff000000".to_string(),
+            line_width: 3.0,
+        }
+    }
+}
+
+pub fn write(route: &Route, options: &KmlOptions) -> Result<String, KmlError> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+
+    let mut kml = BytesStart::new("kml");
+    kml.push_attribute(("xmlns", "http://www.opengis.net/kml/2.2"));
+    writer.write_event(Event::Start(kml))?;
+    writer.write_event(Event::Start(BytesStart::new("Document")))?;
+
+    if let Some(ref name) = route.name {
+        write_text_element(&mut writer, "name", name)?;
+    }
+
+    if let Some(ref description) = options.description {
+        write_text_element(&mut writer, "description", description)?;
+    }
+
+    write_styles(&mut writer, options)?;
+
+    for waypoint in &route.waypoints {
+        write_waypoint(&mut writer, waypoint)?;
+    }
+
+    for track in &route.tracks {
+        write_track(&mut writer, track)?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("Document")))?;
+    writer.write_event(Event::End(BytesEnd::new("kml")))?;
+
+    let result = writer.into_inner().into_inner();
+    String::from_utf8(result).map_err(|e| KmlError::XmlError(e.to_string()))
+}
+
+pub fn write_kmz(route: &Route, options: &KmlOptions) -> Result<Vec<u8>, KmlError> {
+    let kml = write(route, options)?;
+    Ok(kmz::package(&[("doc.kml", kml.as_bytes())]))
+}
+
+fn write_styles<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    options: &KmlOptions,
+) -> Result<(), KmlError> {
+    let mut style = BytesStart::new("Style");
+    style.push_attribute(("id", TRACK_STYLE_ID));
+    writer.write_event(Event::Start(style))?;
+    writer.write_event(Event::Start(BytesStart::new("LineStyle")))?;
+    write_text_element(writer, "color", &options.line_color)?;
+    write_text_element(writer, "width", &options.line_width.to_string())?;
+    writer.write_event(Event::End(BytesEnd::new("LineStyle")))?;
+    writer.write_event(Event::End(BytesEnd::new("Style")))?;
+
+    let mut style = BytesStart::new("Style");
+    style.push_attribute(("id", WAYPOINT_STYLE_ID));
+    writer.write_event(Event::Start(style))?;
+    writer.write_event(Event::Start(BytesStart::new("IconStyle")))?;
+    write_text_element(writer, "color", &options.line_color)?;
+    writer.write_event(Event::End(BytesEnd::new("IconStyle")))?;
+    writer.write_event(Event::End(BytesEnd::new("Style")))?;
+
+    Ok(())
+}
+
+fn write_waypoint<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    waypoint: &Waypoint,
+) -> Result<(), KmlError> {
+    writer.write_event(Event::Start(BytesStart::new("Placemark")))?;
+
+    if let Some(ref name) = waypoint.name {
+        write_text_element(writer, "name", name)?;
+    }
+
+    if let Some(ele) = waypoint.coord.ele {
+        write_text_element(writer, "description", &format!("Elevation: {} m", ele))?;
+    }
+
+    write_text_element(writer, "styleUrl", &format!("#{}", WAYPOINT_STYLE_ID))?;
+
+    writer.write_event(Event::Start(BytesStart::new("Point")))?;
+    write_text_element(writer, "coordinates", &format_coordinate(&waypoint.coord))?;
+    writer.write_event(Event::End(BytesEnd::new("Point")))?;
+
+    writer.write_event(Event::End(BytesEnd::new("Placemark")))?;
+
+    Ok(())
+}
+
+fn write_track<W: std::io::Write>(writer: &mut Writer<W>, track: &Track) -> Result<(), KmlError> {
+    let segments: Vec<_> = track
+        .segments
+        .iter()
+        .filter(|segment| !segment.points.is_empty())
+        .collect();
+
+    if segments.is_empty() {
+        return Ok(());
+    }
+
+    writer.write_event(Event::Start(BytesStart::new("Placemark")))?;
+
+    if let Some(ref name) = track.name {
+        write_text_element(writer, "name", name)?;
+    }
+
+    write_text_element(writer, "styleUrl", &format!("#{}", TRACK_STYLE_ID))?;
+
+    let multi = segments.len() > 1;
+    if multi {
+        writer.write_event(Event::Start(BytesStart::new("MultiGeometry")))?;
+    }
+
+    for segment in segments {
+        let coordinates = segment
+            .points
+            .iter()
+            .map(format_coordinate)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        writer.write_event(Event::Start(BytesStart::new("LineString")))?;
+        write_text_element(writer, "tessellate", "1")?;
+        write_text_element(writer, "coordinates", &coordinates)?;
+        writer.write_event(Event::End(BytesEnd::new("LineString")))?;
+    }
+
+    if multi {
+        writer.write_event(Event::End(BytesEnd::new("MultiGeometry")))?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("Placemark")))?;
+
+    Ok(())
+}
+
+fn write_text_element<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    name: &str,
+    text: &str,
+) -> Result<(), KmlError> {
+    writer.write_event(Event::Start(BytesStart::new(name)))?;
+    writer.write_event(Event::Text(BytesText::new(text)))?;
+    writer.write_event(Event::End(BytesEnd::new(name)))?;
+    Ok(())
+}
+
+fn format_coordinate(coord: &Coordinate) -> String {
+    match coord.ele {
+        Some(ele) => format!("{},{},{}", coord.lon, coord.lat, ele),
+        None => format!("{},{}", coord.lon, coord.lat),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+    use crate::types::TrackSegment;
+
+    #[test]
+    fn test_write_kml_header() {
+        let route = Route::new();
+        let kml = write(&route, &KmlOptions::default()).unwrap();
+        assert!(kml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(kml.contains("<kml xmlns=\"http://www.opengis.net/kml/2.2\">"));
+        assert!(kml.contains("<Document>"));
+        assert!(kml.contains("<color>ff000000</color>"));
+    }
+
+    #[test]
+    fn test_write_waypoint_placemark() {
+        let mut route = Route::with_name("Test Route".to_string());
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(37.7749, -122.4194),
+            "San Francisco".to_string(),
+        ));
+
+        let kml = write(&route, &KmlOptions::default()).unwrap();
+        assert!(kml.contains("<name>Test Route</name>"));
+        assert!(kml.contains("<name>San Francisco</name>"));
+        assert!(kml.contains("<coordinates>-122.4194,37.7749</coordinates>"));
+    }
+
+    #[test]
+    fn test_write_multi_segment_track() {
+        let mut route = Route::new();
+        let segment1 = TrackSegment::new(vec![
+            Coordinate::new(37.7749, -122.4194),
+            Coordinate::new(37.7835, -122.4089),
+        ]);
+        let segment2 = TrackSegment::new(vec![
+            Coordinate::new(40.7128, -74.0060),
+            Coordinate::new(40.7580, -73.9855),
+        ]);
+        route.add_track(Track::new(vec![segment1, segment2]));
+
+        let kml = write(&route, &KmlOptions::default()).unwrap();
+        assert!(kml.contains("<MultiGeometry>"));
+        assert_eq!(kml.matches("<LineString>").count(), 2);
+    }
+
+    #[test]
+    fn test_write_description() {
+        let route = Route::new();
+        let options = KmlOptions {
+            description: Some("Exported route".to_string()),
+            ..KmlOptions::default()
+        };
+        let kml = write(&route, &options).unwrap();
+        assert!(kml.contains("<description>Exported route</description>"));
+    }
+
+    #[test]
+    fn test_round_trip_through_parser() {
+        let mut route = Route::with_name("Loop".to_string());
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::with_elevation(37.7749, -122.4194, 10.0),
+            "Start".to_string(),
+        ));
+        let segment = TrackSegment::new(vec![
+            Coordinate::new(37.7749, -122.4194),
+            Coordinate::new(37.7835, -122.4089),
+            Coordinate::new(37.79, -122.4),
+        ]);
+        route.add_track(Track::with_name("Path".to_string(), vec![segment]));
+
+        let kml = write(&route, &KmlOptions::default()).unwrap();
+        let parsed = parser::kml::parse(&kml).unwrap();
+        assert_eq!(parsed.name, Some("Loop".to_string()));
+        assert_eq!(parsed.waypoints.len(), 1);
+        assert_eq!(parsed.waypoints[0].coord.ele, Some(10.0));
+        assert_eq!(parsed.tracks.len(), 1);
+        assert_eq!(parsed.tracks[0].name, Some("Path".to_string()));
+        assert_eq!(parsed.tracks[0].segments[0].points.len(), 3);
+    }
+
+    #[test]
+    fn test_write_kmz() {
+        let route = Route::with_name("Zipped".to_string());
+        let kmz = write_kmz(&route, &KmlOptions::default()).unwrap();
+        assert_eq!(&kmz[..4], b"PK\x03\x04");
+        let kml = write(&route, &KmlOptions::default()).unwrap();
+        assert!(kmz
+            .windows(kml.len())
+            .any(|window| window == kml.as_bytes()));
+    }
+}
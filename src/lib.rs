@@ -1,6 +1,18 @@
-mod gpx;
-mod parser;
-mod types;
+pub mod analysis;
+pub mod diagnostics;
+pub mod elevation;
+pub mod export;
+pub mod fit;
+pub mod geojson;
+pub mod geometry;
+pub mod gpx;
+pub mod kml;
+pub mod parser;
+pub mod stats;
+pub mod tcx;
+pub mod time;
+pub mod transform;
+pub mod types;
 
 use wasm_bindgen::prelude::*;
 
@@ -15,12 +27,83 @@ pub fn parse_google_maps_url(url: &str) -> Result<JsValue, JsValue> {
     serde_wasm_bindgen::to_value(&route).map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
+#[wasm_bindgen]
+pub fn parse_google_maps_url_with_options(url: &str, options: JsValue) -> Result<JsValue, JsValue> {
+    let options: parser::url::ParseOptions = options_from_js(options)?;
+    let route = parser::url::parse_with_options(url, &options)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&route).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
 #[wasm_bindgen]
 pub fn parse_kml(kml_content: &str) -> Result<JsValue, JsValue> {
     let route = parser::kml::parse(kml_content).map_err(|e| JsValue::from_str(&e.to_string()))?;
     serde_wasm_bindgen::to_value(&route).map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
+#[wasm_bindgen]
+pub fn parse_fit(fit_content: &[u8]) -> Result<JsValue, JsValue> {
+    let route = parser::fit::parse(fit_content).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&route).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn decode_polyline(
+    encoded: &str,
+    precision: Option<u32>,
+    elevation_precision: Option<u32>,
+) -> Result<JsValue, JsValue> {
+    let precision = precision.unwrap_or(parser::polyline::DEFAULT_PRECISION);
+    let coords = match elevation_precision {
+        Some(ele) => parser::polyline::decode_with_elevation(encoded, precision, ele),
+        None => parser::polyline::decode(encoded, precision),
+    }
+    .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&coords).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn encode_polyline(
+    coordinates: JsValue,
+    precision: Option<u32>,
+    elevation_precision: Option<u32>,
+) -> Result<String, JsValue> {
+    let coords: Vec<types::Coordinate> = serde_wasm_bindgen::from_value(coordinates)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let precision = precision.unwrap_or(parser::polyline::DEFAULT_PRECISION);
+    match elevation_precision {
+        Some(ele) => parser::polyline::encode_with_elevation(&coords, precision, ele),
+        None => parser::polyline::encode(&coords, precision),
+    }
+    .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn decode_flexible_polyline(encoded: &str) -> Result<JsValue, JsValue> {
+    let coords =
+        parser::flexpolyline::decode(encoded).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&coords).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn encode_flexible_polyline(
+    coordinates: JsValue,
+    precision: Option<u32>,
+    elevation_precision: Option<u32>,
+) -> Result<String, JsValue> {
+    let coords: Vec<types::Coordinate> = serde_wasm_bindgen::from_value(coordinates)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let mut header = parser::flexpolyline::Header::default();
+    if let Some(precision) = precision {
+        header.precision = precision;
+    }
+    if let Some(ele) = elevation_precision {
+        header.third_dimension = parser::flexpolyline::ThirdDimension::Elevation;
+        header.third_dimension_precision = ele;
+    }
+    parser::flexpolyline::encode(&coords, &header).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
 #[wasm_bindgen]
 pub fn generate_gpx(route_json: &str) -> Result<String, JsValue> {
     let route: types::Route =
@@ -28,10 +111,365 @@ pub fn generate_gpx(route_json: &str) -> Result<String, JsValue> {
     gpx::write(&route).map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
+/// Writes the route as GPX. `options` is an optional `ConvertOptions`
+/// object, e.g. `{ markers: { interval: 5 } }`.
 #[wasm_bindgen]
-pub fn route_to_gpx(route: JsValue) -> Result<String, JsValue> {
+pub fn route_to_gpx(route: JsValue, options: JsValue) -> Result<String, JsValue> {
     let route: types::Route =
         serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
-    gpx::write(&route).map_err(|e| JsValue::from_str(&e.to_string()))
+    let options: export::ConvertOptions = options_from_js(options)?;
+    gpx::write(&options.apply(&route)).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn route_to_geojson(route: JsValue, split_antimeridian: bool) -> Result<String, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options = geojson::GeoJsonOptions { split_antimeridian };
+    geojson::write(&route, &options).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn route_to_kml(route: JsValue) -> Result<String, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    kml::write(&route, &kml::KmlOptions::default()).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn route_to_kmz(route: JsValue) -> Result<Vec<u8>, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    kml::write_kmz(&route, &kml::KmlOptions::default())
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn route_to_tcx(route: JsValue) -> Result<String, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    tcx::write(&route).map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
+#[wasm_bindgen]
+pub fn route_to_fit(route: JsValue) -> Result<Vec<u8>, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    fit::write(&route).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn convert_route(route: JsValue, format: &str, options: JsValue) -> Result<Vec<u8>, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let format: export::ExportFormat = format
+        .parse()
+        .map_err(|e: export::ExportError| JsValue::from_str(&e.to_string()))?;
+    let options: export::ConvertOptions = options_from_js(options)?;
+    export::convert(&route, format, &options).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn simplify_route(route: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: geometry::simplify::SimplifyOptions = options_from_js(options)?;
+    let simplified = geometry::simplify::simplify_route(&route, &options);
+    serde_wasm_bindgen::to_value(&simplified).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn resample_route(route: JsValue, spacing: f64) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let resampled = geometry::resample::resample_route(&route, spacing);
+    serde_wasm_bindgen::to_value(&resampled).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn smooth_elevation(route: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let mut route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: elevation::smooth::SmoothOptions = options_from_js(options)?;
+    let stats = elevation::smooth::smooth_route(&mut route, &options);
+    serde_wasm_bindgen::to_value(&Processed {
+        route,
+        report: stats,
+    })
+    .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Assigns synthetic timestamps starting at `start_time` (milliseconds since
+/// the Unix epoch, as returned by `Date.now()`).
+#[wasm_bindgen]
+pub fn add_timestamps(
+    route: JsValue,
+    start_time: f64,
+    options: JsValue,
+) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: transform::timestamps::TimestampOptions = options_from_js(options)?;
+    let timed = transform::timestamps::add_timestamps(&route, start_time as i64, &options);
+    serde_wasm_bindgen::to_value(&timed).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn snap_waypoints(route: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: geometry::snap::SnapOptions = options_from_js(options)?;
+    let (route, positions) = geometry::snap::snap_waypoints(&route, &options);
+    serde_wasm_bindgen::to_value(&Processed {
+        route,
+        report: positions,
+    })
+    .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn route_climbs(route: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: analysis::climbs::ClimbOptions = options_from_js(options)?;
+    serde_wasm_bindgen::to_value(&analysis::climbs::detect(&route, &options))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Compares two routes; returns `null` when either has no track points.
+#[wasm_bindgen]
+pub fn compare_routes(
+    first: JsValue,
+    second: JsValue,
+    options: JsValue,
+) -> Result<JsValue, JsValue> {
+    let first: types::Route =
+        serde_wasm_bindgen::from_value(first).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let second: types::Route =
+        serde_wasm_bindgen::from_value(second).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: analysis::compare::CompareOptions = options_from_js(options)?;
+    serde_wasm_bindgen::to_value(&analysis::compare::compare(&first, &second, &options))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Renders both routes and their divergent stretches as `gpx` or `geojson`.
+#[wasm_bindgen]
+pub fn route_diff_overlay(
+    first: JsValue,
+    second: JsValue,
+    format: &str,
+    options: JsValue,
+) -> Result<String, JsValue> {
+    let first: types::Route =
+        serde_wasm_bindgen::from_value(first).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let second: types::Route =
+        serde_wasm_bindgen::from_value(second).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: analysis::compare::CompareOptions = options_from_js(options)?;
+    let comparison = analysis::compare::compare(&first, &second, &options)
+        .ok_or_else(|| JsValue::from_str("Both routes need track points to compare"))?;
+    let overlay = analysis::compare::overlay(&first, &second, &comparison);
+
+    let format: export::ExportFormat = format
+        .parse()
+        .map_err(|e: export::ExportError| JsValue::from_str(&e.to_string()))?;
+    match format {
+        export::ExportFormat::Gpx => {
+            gpx::write(&overlay).map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+        export::ExportFormat::GeoJson => geojson::write(&overlay, &Default::default())
+            .map_err(|e| JsValue::from_str(&e.to_string())),
+        _ => Err(JsValue::from_str(&format!(
+            "Unsupported overlay format: {}",
+            format.extension()
+        ))),
+    }
+}
+
+#[wasm_bindgen]
+pub fn route_cues(route: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: analysis::cues::CueOptions = options_from_js(options)?;
+    serde_wasm_bindgen::to_value(&analysis::cues::detect(&route, &options))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Renders a printable cue sheet as `csv` or `html`.
+#[wasm_bindgen]
+pub fn route_cue_sheet(route: JsValue, format: &str, options: JsValue) -> Result<String, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: analysis::cues::CueOptions = options_from_js(options)?;
+    let cues = analysis::cues::detect(&route, &options);
+    match format.to_ascii_lowercase().as_str() {
+        "csv" => Ok(analysis::cues::to_csv(&cues)),
+        "html" => Ok(analysis::cues::to_html(
+            &cues,
+            route.name.as_deref().unwrap_or("Cue sheet"),
+        )),
+        _ => Err(JsValue::from_str(&format!(
+            "Unsupported cue sheet format: {}",
+            format
+        ))),
+    }
+}
+
+#[wasm_bindgen]
+pub fn reverse_route(route: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&transform::reverse::reverse(&route))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Joins an array of route objects into one route.
+#[wasm_bindgen]
+pub fn concatenate_routes(routes: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let routes: Vec<types::Route> =
+        serde_wasm_bindgen::from_value(routes).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: transform::join::ConcatOptions = options_from_js(options)?;
+    serde_wasm_bindgen::to_value(&transform::join::concatenate(&routes, &options))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Merges the tracks of each route in an array whose ends touch within
+/// `tolerance` metres, then joins the routes into one.
+#[wasm_bindgen]
+pub fn merge_routes(routes: JsValue, tolerance: f64) -> Result<JsValue, JsValue> {
+    let routes: Vec<types::Route> =
+        serde_wasm_bindgen::from_value(routes).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let joined = transform::join::concatenate(&routes, &Default::default());
+    serde_wasm_bindgen::to_value(&transform::join::merge_tracks(&joined, tolerance))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Keeps the section between `from` and `to` metres along the route.
+#[wasm_bindgen]
+pub fn slice_route(route: JsValue, from: f64, to: f64) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&transform::slice::slice_route(&route, from, to))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Keeps the section between the route points closest to two coordinates.
+#[wasm_bindgen]
+pub fn slice_route_between(
+    route: JsValue,
+    start: JsValue,
+    end: JsValue,
+) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let start: types::Coordinate =
+        serde_wasm_bindgen::from_value(start).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let end: types::Coordinate =
+        serde_wasm_bindgen::from_value(end).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&transform::slice::slice_route_between(&route, &start, &end))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Clips the route to a polygon given as an array of `{ lat, lon }` objects.
+#[wasm_bindgen]
+pub fn clip_route_to_polygon(route: JsValue, polygon: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let ring: Vec<types::Coordinate> =
+        serde_wasm_bindgen::from_value(polygon).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let polygon = transform::clip::Polygon::new(ring);
+    serde_wasm_bindgen::to_value(&transform::clip::clip_route(&route, &polygon))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Clips the route to a `{ min_lat, min_lon, max_lat, max_lon }` box.
+#[wasm_bindgen]
+pub fn clip_route_to_bounds(route: JsValue, bounds: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let bounds: stats::Bounds =
+        serde_wasm_bindgen::from_value(bounds).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let polygon = transform::clip::Polygon::from_bounds(&bounds);
+    serde_wasm_bindgen::to_value(&transform::clip::clip_route(&route, &polygon))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn apply_privacy_zones(route: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: transform::privacy::PrivacyOptions = options_from_js(options)?;
+    serde_wasm_bindgen::to_value(&transform::privacy::apply_privacy(&route, &options))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn clean_route(route: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: transform::cleanup::CleanupOptions = options_from_js(options)?;
+    let (route, diagnostics) = transform::cleanup::cleanup(&route, &options);
+    serde_wasm_bindgen::to_value(&Processed {
+        route,
+        report: diagnostics,
+    })
+    .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn remove_outliers(route: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: transform::outliers::OutlierOptions = options_from_js(options)?;
+    let (route, report) = transform::outliers::filter_route(&route, &options);
+    serde_wasm_bindgen::to_value(&Processed { route, report })
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Converts a route between the WGS-84, GCJ-02 and BD-09 datums.
+#[wasm_bindgen]
+pub fn convert_route_datum(route: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: transform::datum::DatumOptions = options_from_js(options)?;
+    serde_wasm_bindgen::to_value(&transform::datum::convert_route(&route, &options))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn split_route_to_gpx(route: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: transform::split::SplitOptions = options_from_js(options)?;
+    let files = transform::split::split_route(&route, &options)
+        .iter()
+        .map(gpx::write)
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&files).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn route_stats(route: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let route: types::Route =
+        serde_wasm_bindgen::from_value(route).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let options: stats::StatsOptions = options_from_js(options)?;
+    serde_wasm_bindgen::to_value(&stats::compute(&route, &options))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// A transformed route returned to JavaScript together with what was done
+/// to it.
+#[derive(serde::Serialize)]
+struct Processed<T> {
+    route: types::Route,
+    report: T,
+}
+
+/// Reads an optional options object, falling back to defaults when the
+/// caller passes `undefined` or `null`.
+fn options_from_js<T: serde::de::DeserializeOwned + Default>(value: JsValue) -> Result<T, JsValue> {
+    if value.is_undefined() || value.is_null() {
+        return Ok(T::default());
+    }
+    serde_wasm_bindgen::from_value(value).map_err(|e| JsValue::from_str(&e.to_string()))
+}
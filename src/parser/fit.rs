@@ -0,0 +1,512 @@
+use crate::fit::profile::*;
+use crate::fit::{crc16, semicircles_to_degrees};
+use crate::types::{Coordinate, Route, Track, TrackSegment, Waypoint};
+use thiserror::Error;
+
+const COMPRESSED_HEADER: u8 = 0x80;
+const DEFINITION_HEADER: u8 = 0x40;
+const DEVELOPER_DATA_FLAG: u8 = 0x20;
+const INVALID_SINT32: i32 = 0x7FFF_FFFF;
+const INVALID_UINT16: u16 = 0xFFFF;
+const INVALID_UINT32: u32 = 0xFFFF_FFFF;
+
+#[derive(Error, Debug)]
+pub enum FitParseError {
+    #[error("Invalid FIT header")]
+    InvalidHeader,
+    #[error("Unexpected end of input")]
+    UnexpectedEnd,
+    #[error("FIT file CRC mismatch")]
+    CrcMismatch,
+    #[error("Data message uses undefined local message type {0}")]
+    UndefinedLocalMessage(u8),
+}
+
+struct FieldDefinition {
+    number: u8,
+    size: usize,
+}
+
+struct Definition {
+    global: u16,
+    big_endian: bool,
+    fields: Vec<FieldDefinition>,
+    // Developer fields are skipped, so only their total size matters.
+    developer_size: usize,
+}
+
+/// Raw field values of one data message, in definition order.
+struct Message<'a> {
+    definition: &'a Definition,
+    values: Vec<&'a [u8]>,
+}
+
+impl Message<'_> {
+    fn field(&self, number: u8) -> Option<&[u8]> {
+        self.definition
+            .fields
+            .iter()
+            .position(|f| f.number == number)
+            .map(|i| self.values[i])
+    }
+
+    fn u16(&self, number: u8) -> Option<u16> {
+        let bytes: [u8; 2] = self.field(number)?.get(..2)?.try_into().ok()?;
+        let value = if self.definition.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        };
+        (value != INVALID_UINT16).then_some(value)
+    }
+
+    fn u32(&self, number: u8) -> Option<u32> {
+        let bytes: [u8; 4] = self.field(number)?.get(..4)?.try_into().ok()?;
+        let value = if self.definition.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        };
+        (value != INVALID_UINT32).then_some(value)
+    }
+
+    fn i32(&self, number: u8) -> Option<i32> {
+        let bytes: [u8; 4] = self.field(number)?.get(..4)?.try_into().ok()?;
+        let value = if self.definition.big_endian {
+            i32::from_be_bytes(bytes)
+        } else {
+            i32::from_le_bytes(bytes)
+        };
+        (value != INVALID_SINT32).then_some(value)
+    }
+
+    fn string(&self, number: u8) -> Option<String> {
+        let bytes = self.field(number)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        let text = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+        (!text.is_empty()).then_some(text)
+    }
+
+    fn position(&self, lat_field: u8, lon_field: u8) -> Option<(f64, f64)> {
+        let lat = self.i32(lat_field)?;
+        let lon = self.i32(lon_field)?;
+        Some((semicircles_to_degrees(lat), semicircles_to_degrees(lon)))
+    }
+}
+
+pub fn parse(bytes: &[u8]) -> Result<Route, FitParseError> {
+    let mut route = Route::new();
+    let mut points = Vec::new();
+    let mut offset = 0;
+
+    // A FIT file may contain several chained files back to back.
+    while offset < bytes.len() {
+        offset += parse_file(&bytes[offset..], &mut route, &mut points)?;
+    }
+
+    if !points.is_empty() {
+        route.add_track(Track::new(vec![TrackSegment::new(points)]));
+    }
+
+    Ok(route)
+}
+
+fn parse_file(
+    bytes: &[u8],
+    route: &mut Route,
+    points: &mut Vec<Coordinate>,
+) -> Result<usize, FitParseError> {
+    let header_size = *bytes.first().ok_or(FitParseError::UnexpectedEnd)? as usize;
+    if header_size < 12 || bytes.len() < header_size || &bytes[8..12] != DATA_TYPE {
+        return Err(FitParseError::InvalidHeader);
+    }
+
+    let data_size = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+    // The data size is untrusted, so guard against it overflowing on 32-bit
+    // targets.
+    let end = header_size
+        .checked_add(data_size)
+        .filter(|end| end.checked_add(2).is_some_and(|total| total <= bytes.len()))
+        .ok_or(FitParseError::UnexpectedEnd)?;
+
+    let stored_crc = u16::from_le_bytes([bytes[end], bytes[end + 1]]);
+    if stored_crc != 0 && crc16(&bytes[..end]) != stored_crc {
+        return Err(FitParseError::CrcMismatch);
+    }
+
+    let data = &bytes[header_size..end];
+    let mut definitions: [Option<Definition>; 16] = Default::default();
+    let mut last_timestamp: Option<u32> = None;
+    let mut index = 0;
+
+    while index < data.len() {
+        let header = data[index];
+        index += 1;
+
+        if header & COMPRESSED_HEADER != 0 {
+            let local = (header >> 5) & 0x03;
+            let time_offset = (header & 0x1F) as u32;
+            let timestamp = last_timestamp.map(|last| {
+                let mut timestamp = (last & !0x1F) | time_offset;
+                if time_offset < (last & 0x1F) {
+                    timestamp = timestamp.wrapping_add(0x20);
+                }
+                timestamp
+            });
+            last_timestamp = timestamp.or(last_timestamp);
+
+            let definition = definitions[local as usize]
+                .as_ref()
+                .ok_or(FitParseError::UndefinedLocalMessage(local))?;
+            let (message, next) = read_message(data, index, definition)?;
+            index = next;
+            handle_message(&message, timestamp, route, points);
+        } else if header & DEFINITION_HEADER != 0 {
+            let local = header & 0x0F;
+            let (definition, next) =
+                read_definition(data, index, header & DEVELOPER_DATA_FLAG != 0)?;
+            index = next;
+            definitions[local as usize] = Some(definition);
+        } else {
+            let local = header & 0x0F;
+            let definition = definitions[local as usize]
+                .as_ref()
+                .ok_or(FitParseError::UndefinedLocalMessage(local))?;
+            let (message, next) = read_message(data, index, definition)?;
+            index = next;
+
+            if let Some(timestamp) = message.u32(FIELD_TIMESTAMP) {
+                last_timestamp = Some(timestamp);
+            }
+            handle_message(&message, last_timestamp, route, points);
+        }
+    }
+
+    Ok(end + 2)
+}
+
+fn read_definition(
+    data: &[u8],
+    start: usize,
+    has_developer_fields: bool,
+) -> Result<(Definition, usize), FitParseError> {
+    let fixed = data
+        .get(start..start + 5)
+        .ok_or(FitParseError::UnexpectedEnd)?;
+    let big_endian = fixed[1] == 1;
+    let global = if big_endian {
+        u16::from_be_bytes([fixed[2], fixed[3]])
+    } else {
+        u16::from_le_bytes([fixed[2], fixed[3]])
+    };
+    let count = fixed[4] as usize;
+    let mut index = start + 5;
+
+    let raw = data
+        .get(index..index + count * 3)
+        .ok_or(FitParseError::UnexpectedEnd)?;
+    let fields = raw
+        .chunks(3)
+        .map(|f| FieldDefinition {
+            number: f[0],
+            size: f[1] as usize,
+        })
+        .collect();
+    index += count * 3;
+
+    let mut developer_size = 0;
+    if has_developer_fields {
+        let count = *data.get(index).ok_or(FitParseError::UnexpectedEnd)? as usize;
+        index += 1;
+        let raw = data
+            .get(index..index + count * 3)
+            .ok_or(FitParseError::UnexpectedEnd)?;
+        developer_size = raw.chunks(3).map(|f| f[1] as usize).sum();
+        index += count * 3;
+    }
+
+    Ok((
+        Definition {
+            global,
+            big_endian,
+            fields,
+            developer_size,
+        },
+        index,
+    ))
+}
+
+fn read_message<'a>(
+    data: &'a [u8],
+    start: usize,
+    definition: &'a Definition,
+) -> Result<(Message<'a>, usize), FitParseError> {
+    let mut index = start;
+    let mut values = Vec::with_capacity(definition.fields.len());
+
+    for field in &definition.fields {
+        let value = data
+            .get(index..index + field.size)
+            .ok_or(FitParseError::UnexpectedEnd)?;
+        values.push(value);
+        index += field.size;
+    }
+
+    index += definition.developer_size;
+    if index > data.len() {
+        return Err(FitParseError::UnexpectedEnd);
+    }
+
+    Ok((Message { definition, values }, index))
+}
+
+fn handle_message(
+    message: &Message,
+    timestamp: Option<u32>,
+    route: &mut Route,
+    points: &mut Vec<Coordinate>,
+) {
+    match message.definition.global {
+        MESG_RECORD => {
+            if let Some((lat, lon)) = message.position(RECORD_POSITION_LAT, RECORD_POSITION_LONG) {
+                let ele = message
+                    .u32(RECORD_ENHANCED_ALTITUDE)
+                    .map(|a| a as f64)
+                    .or_else(|| message.u16(RECORD_ALTITUDE).map(|a| a as f64))
+                    .map(|a| a / ALTITUDE_SCALE - ALTITUDE_OFFSET);
+                points.push(Coordinate {
+                    lat,
+                    lon,
+                    ele,
+                    time: timestamp.map(fit_time_to_unix_millis),
+                });
+            }
+        }
+        MESG_COURSE_POINT => {
+            if let Some((lat, lon)) =
+                message.position(COURSE_POINT_POSITION_LAT, COURSE_POINT_POSITION_LONG)
+            {
+                let coord = Coordinate {
+                    lat,
+                    lon,
+                    ele: None,
+                    time: message
+                        .u32(COURSE_POINT_TIMESTAMP)
+                        .map(fit_time_to_unix_millis),
+                };
+                let waypoint = match message.string(COURSE_POINT_NAME) {
+                    Some(name) => Waypoint::with_name(coord, name),
+                    None => Waypoint::new(coord),
+                };
+                route.add_waypoint(waypoint);
+            }
+        }
+        MESG_COURSE => {
+            if let Some(name) = message.string(COURSE_NAME) {
+                route.name = Some(name);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn fit_time_to_unix_millis(timestamp: u32) -> i64 {
+    (timestamp as i64 + FIT_EPOCH_OFFSET) * 1000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fit::degrees_to_semicircles;
+
+    // Builds a FIT file around raw message bytes, filling in header and CRCs.
+    fn fit_file(data: &[u8]) -> Vec<u8> {
+        let mut file = vec![HEADER_SIZE, PROTOCOL_VERSION];
+        file.extend_from_slice(&PROFILE_VERSION.to_le_bytes());
+        file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        file.extend_from_slice(DATA_TYPE);
+        let header_crc = crc16(&file);
+        file.extend_from_slice(&header_crc.to_le_bytes());
+        file.extend_from_slice(data);
+        let crc = crc16(&file);
+        file.extend_from_slice(&crc.to_le_bytes());
+        file
+    }
+
+    fn record_definition(local: u8, developer_fields: &[(u8, u8, u8)]) -> Vec<u8> {
+        let mut header = DEFINITION_HEADER | local;
+        if !developer_fields.is_empty() {
+            header |= DEVELOPER_DATA_FLAG;
+        }
+        let mut data = vec![header, 0, 0];
+        data.extend_from_slice(&MESG_RECORD.to_le_bytes());
+        data.push(4);
+        data.extend_from_slice(&[FIELD_TIMESTAMP, 4, BASE_UINT32]);
+        data.extend_from_slice(&[RECORD_POSITION_LAT, 4, BASE_SINT32]);
+        data.extend_from_slice(&[RECORD_POSITION_LONG, 4, BASE_SINT32]);
+        data.extend_from_slice(&[RECORD_ALTITUDE, 2, BASE_UINT16]);
+        if !developer_fields.is_empty() {
+            data.push(developer_fields.len() as u8);
+            for field in developer_fields {
+                data.extend_from_slice(&[field.0, field.1, field.2]);
+            }
+        }
+        data
+    }
+
+    fn record_values(timestamp: u32, lat: f64, lon: f64, ele: f64) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&timestamp.to_le_bytes());
+        data.extend_from_slice(&degrees_to_semicircles(lat).to_le_bytes());
+        data.extend_from_slice(&degrees_to_semicircles(lon).to_le_bytes());
+        let altitude = ((ele + ALTITUDE_OFFSET) * ALTITUDE_SCALE) as u16;
+        data.extend_from_slice(&altitude.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_records() {
+        let mut data = record_definition(0, &[]);
+        data.push(0);
+        data.extend(record_values(1_000_000_000, 37.7749, -122.4194, 12.0));
+        data.push(0);
+        data.extend(record_values(1_000_000_005, 37.7835, -122.4089, 15.0));
+
+        let route = parse(&fit_file(&data)).unwrap();
+        let points = &route.tracks[0].segments[0].points;
+        assert_eq!(points.len(), 2);
+        assert!((points[0].lat - 37.7749).abs() < 1e-6);
+        assert!((points[1].lon - (-122.4089)).abs() < 1e-6);
+        assert_eq!(points[0].ele, Some(12.0));
+        assert_eq!(
+            points[1].time,
+            Some((1_000_000_005 + FIT_EPOCH_OFFSET) * 1000)
+        );
+    }
+
+    #[test]
+    fn test_parse_compressed_timestamp_header() {
+        let mut data = record_definition(0, &[]);
+        data.push(0);
+        data.extend(record_values(1_000_000_030, 0.0, 0.0, 0.0));
+
+        // Compressed-header record: local type 1 has no timestamp field.
+        data.extend_from_slice(&[DEFINITION_HEADER | 1, 0, 0]);
+        data.extend_from_slice(&MESG_RECORD.to_le_bytes());
+        data.push(2);
+        data.extend_from_slice(&[RECORD_POSITION_LAT, 4, BASE_SINT32]);
+        data.extend_from_slice(&[RECORD_POSITION_LONG, 4, BASE_SINT32]);
+        // Offset 2 is below the last timestamp's low bits (30), so it rolls over.
+        data.push(COMPRESSED_HEADER | (1 << 5) | 2);
+        data.extend_from_slice(&degrees_to_semicircles(0.001).to_le_bytes());
+        data.extend_from_slice(&degrees_to_semicircles(0.001).to_le_bytes());
+
+        let route = parse(&fit_file(&data)).unwrap();
+        let points = &route.tracks[0].segments[0].points;
+        assert_eq!(points.len(), 2);
+        let first = points[0].time.unwrap();
+        assert_eq!(points[1].time.unwrap() - first, 4000);
+    }
+
+    #[test]
+    fn test_parse_skips_developer_fields() {
+        let mut data = record_definition(0, &[(0, 3, 0)]);
+        data.push(0);
+        data.extend(record_values(1_000_000_000, 1.0, 2.0, 3.0));
+        data.extend_from_slice(&[0xAA, 0xBB, 0xCC]);
+        data.push(0);
+        data.extend(record_values(1_000_000_001, 1.5, 2.5, 3.5));
+        data.extend_from_slice(&[0xAA, 0xBB, 0xCC]);
+
+        let route = parse(&fit_file(&data)).unwrap();
+        let points = &route.tracks[0].segments[0].points;
+        assert_eq!(points.len(), 2);
+        assert!((points[1].lat - 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_parse_skips_records_without_position() {
+        let mut data = record_definition(0, &[]);
+        data.push(0);
+        data.extend_from_slice(&1_000_000_000u32.to_le_bytes());
+        data.extend_from_slice(&INVALID_SINT32.to_le_bytes());
+        data.extend_from_slice(&INVALID_SINT32.to_le_bytes());
+        data.extend_from_slice(&INVALID_UINT16.to_le_bytes());
+
+        let route = parse(&fit_file(&data)).unwrap();
+        assert!(route.tracks.is_empty());
+    }
+
+    #[test]
+    fn test_parse_crc_mismatch() {
+        let mut file = fit_file(&record_definition(0, &[]));
+        let last = file.len() - 1;
+        file[last] ^= 0xFF;
+        assert!(matches!(parse(&file), Err(FitParseError::CrcMismatch)));
+    }
+
+    #[test]
+    fn test_parse_oversized_data_size() {
+        let mut file = fit_file(&record_definition(0, &[]));
+        file[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        let last = file.len() - 2;
+        file[last..].copy_from_slice(&[0, 0]);
+        assert!(matches!(parse(&file), Err(FitParseError::UnexpectedEnd)));
+    }
+
+    #[test]
+    fn test_parse_compressed_timestamp_at_u32_limit() {
+        let mut data = record_definition(0, &[]);
+        data.push(0);
+        data.extend(record_values(u32::MAX - 2, 0.0, 0.0, 0.0));
+
+        data.extend_from_slice(&[DEFINITION_HEADER | 1, 0, 0]);
+        data.extend_from_slice(&MESG_RECORD.to_le_bytes());
+        data.push(2);
+        data.extend_from_slice(&[RECORD_POSITION_LAT, 4, BASE_SINT32]);
+        data.extend_from_slice(&[RECORD_POSITION_LONG, 4, BASE_SINT32]);
+        data.push(COMPRESSED_HEADER | (1 << 5) | 2);
+        data.extend_from_slice(&degrees_to_semicircles(0.001).to_le_bytes());
+        data.extend_from_slice(&degrees_to_semicircles(0.001).to_le_bytes());
+
+        let route = parse(&fit_file(&data)).unwrap();
+        assert_eq!(route.tracks[0].segments[0].points.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_invalid_header() {
+        assert!(matches!(
+            parse(b"not a fit file at all"),
+            Err(FitParseError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_course_encoder() {
+        let mut route = Route::with_name("Hill Repeats".to_string());
+        let segment = TrackSegment::new(vec![
+            Coordinate::with_elevation(0.0, 0.0, 10.0),
+            Coordinate::with_elevation(0.0, 0.01, 20.0),
+            Coordinate::with_elevation(0.0, 0.02, 15.0),
+        ]);
+        route.add_track(Track::new(vec![segment]));
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, 0.015),
+            "Summit".to_string(),
+        ));
+
+        let parsed = parse(&crate::fit::write(&route).unwrap()).unwrap();
+        assert_eq!(parsed.name, Some("Hill Repeats".to_string()));
+        assert_eq!(parsed.waypoints.len(), 1);
+        assert_eq!(parsed.waypoints[0].name, Some("Summit".to_string()));
+
+        let points = &parsed.tracks[0].segments[0].points;
+        let original = &route.tracks[0].segments[0].points;
+        assert_eq!(points.len(), original.len());
+        for (a, b) in points.iter().zip(original) {
+            assert!((a.lat - b.lat).abs() < 1e-7);
+            assert!((a.lon - b.lon).abs() < 1e-7);
+            assert!((a.ele.unwrap() - b.ele.unwrap()).abs() < 0.2);
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use crate::parser::polyline::{
+    decode_varint, encode_varint, zigzag_decode, zigzag_encode, PolylineError, MAX_PRECISION,
+};
+use crate::types::Coordinate;
+use thiserror::Error;
+
+const FORMAT_VERSION: i64 = 1;
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Error, Debug)]
+pub enum FlexPolylineError {
+    #[error("Invalid flexible polyline encoding")]
+    InvalidEncoding,
+    #[error("Unexpected end of input")]
+    UnexpectedEnd,
+    #[error("Unsupported flexible polyline version: {0}")]
+    UnsupportedVersion(i64),
+    #[error("Invalid flexible polyline header")]
+    InvalidHeader,
+    #[error("Precision must be between 0 and 15")]
+    InvalidPrecision,
+}
+
+impl From<PolylineError> for FlexPolylineError {
+    fn from(e: PolylineError) -> Self {
+        match e {
+            PolylineError::InvalidEncoding => FlexPolylineError::InvalidEncoding,
+            PolylineError::UnexpectedEnd => FlexPolylineError::UnexpectedEnd,
+            PolylineError::InvalidPrecision(_) => FlexPolylineError::InvalidPrecision,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThirdDimension {
+    Absent,
+    Level,
+    Altitude,
+    Elevation,
+    Custom1,
+    Custom2,
+}
+
+impl ThirdDimension {
+    fn from_code(code: i64) -> Option<Self> {
+        match code {
+            0 => Some(ThirdDimension::Absent),
+            1 => Some(ThirdDimension::Level),
+            2 => Some(ThirdDimension::Altitude),
+            3 => Some(ThirdDimension::Elevation),
+            6 => Some(ThirdDimension::Custom1),
+            7 => Some(ThirdDimension::Custom2),
+            _ => None,
+        }
+    }
+
+    fn code(&self) -> i64 {
+        match self {
+            ThirdDimension::Absent => 0,
+            ThirdDimension::Level => 1,
+            ThirdDimension::Altitude => 2,
+            ThirdDimension::Elevation => 3,
+            ThirdDimension::Custom1 => 6,
+            ThirdDimension::Custom2 => 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub precision: u32,
+    pub third_dimension: ThirdDimension,
+    pub third_dimension_precision: u32,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self {
+            precision: 5,
+            third_dimension: ThirdDimension::Absent,
+            third_dimension_precision: 0,
+        }
+    }
+}
+
+/// Decodes a flexible polyline. Any third dimension is stored as elevation.
+pub fn decode(encoded: &str) -> Result<Vec<Coordinate>, FlexPolylineError> {
+    decode_with_header(encoded).map(|(_, coordinates)| coordinates)
+}
+
+pub fn decode_with_header(encoded: &str) -> Result<(Header, Vec<Coordinate>), FlexPolylineError> {
+    let bytes = encoded.as_bytes();
+
+    let (version, index) = decode_unsigned(bytes, 0)?;
+    if version != FORMAT_VERSION {
+        return Err(FlexPolylineError::UnsupportedVersion(version));
+    }
+
+    let (content, mut index) = decode_unsigned(bytes, index)?;
+    let header = Header {
+        precision: (content & 0x0F) as u32,
+        third_dimension: ThirdDimension::from_code((content >> 4) & 0x07)
+            .ok_or(FlexPolylineError::InvalidHeader)?,
+        third_dimension_precision: ((content >> 7) & 0x0F) as u32,
+    };
+
+    let factor = 10f64.powi(header.precision as i32);
+    let third_factor = 10f64.powi(header.third_dimension_precision as i32);
+    let has_third = header.third_dimension != ThirdDimension::Absent;
+
+    let mut coordinates = Vec::new();
+    let mut lat: i64 = 0;
+    let mut lng: i64 = 0;
+    let mut third: i64 = 0;
+
+    while index < bytes.len() {
+        let (delta, next) = decode_signed(bytes, index)?;
+        lat = lat.wrapping_add(delta);
+        let (delta, next) = decode_signed(bytes, next)?;
+        lng = lng.wrapping_add(delta);
+        index = next;
+
+        let coordinate = if has_third {
+            let (delta, next) = decode_signed(bytes, index)?;
+            third = third.wrapping_add(delta);
+            index = next;
+            Coordinate::with_elevation(
+                lat as f64 / factor,
+                lng as f64 / factor,
+                third as f64 / third_factor,
+            )
+        } else {
+            Coordinate::new(lat as f64 / factor, lng as f64 / factor)
+        };
+
+        coordinates.push(coordinate);
+    }
+
+    Ok((header, coordinates))
+}
+
+/// Encodes coordinates as a flexible polyline. With a third dimension,
+/// missing elevations are written as zero.
+pub fn encode(coordinates: &[Coordinate], header: &Header) -> Result<String, FlexPolylineError> {
+    if header.precision > MAX_PRECISION || header.third_dimension_precision > MAX_PRECISION {
+        return Err(FlexPolylineError::InvalidPrecision);
+    }
+
+    let mut encoded = String::new();
+    encode_unsigned(FORMAT_VERSION, &mut encoded);
+    let content = (header.third_dimension_precision as i64) << 7
+        | header.third_dimension.code() << 4
+        | header.precision as i64;
+    encode_unsigned(content, &mut encoded);
+
+    let factor = 10f64.powi(header.precision as i32);
+    let third_factor = 10f64.powi(header.third_dimension_precision as i32);
+    let has_third = header.third_dimension != ThirdDimension::Absent;
+
+    let mut prev_lat: i64 = 0;
+    let mut prev_lng: i64 = 0;
+    let mut prev_third: i64 = 0;
+
+    for coordinate in coordinates {
+        let lat = (coordinate.lat * factor).round() as i64;
+        let lng = (coordinate.lon * factor).round() as i64;
+        encode_unsigned(zigzag_encode(lat.wrapping_sub(prev_lat)), &mut encoded);
+        encode_unsigned(zigzag_encode(lng.wrapping_sub(prev_lng)), &mut encoded);
+        prev_lat = lat;
+        prev_lng = lng;
+
+        if has_third {
+            let third = (coordinate.ele.unwrap_or(0.0) * third_factor).round() as i64;
+            encode_unsigned(zigzag_encode(third.wrapping_sub(prev_third)), &mut encoded);
+            prev_third = third;
+        }
+    }
+
+    Ok(encoded)
+}
+
+fn decode_unsigned(bytes: &[u8], start: usize) -> Result<(i64, usize), FlexPolylineError> {
+    Ok(decode_varint(bytes, start, |byte| {
+        ALPHABET.iter().position(|&c| c == byte).map(|i| i as u8)
+    })?)
+}
+
+fn decode_signed(bytes: &[u8], start: usize) -> Result<(i64, usize), FlexPolylineError> {
+    let (value, index) = decode_unsigned(bytes, start)?;
+    Ok((zigzag_decode(value), index))
+}
+
+fn encode_unsigned(value: i64, output: &mut String) {
+    encode_varint(value, output, |chunk| ALPHABET[chunk as usize] as char);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[Coordinate], expected: &[(f64, f64)]) {
+        assert_eq!(actual.len(), expected.len());
+        for (coord, (lat, lon)) in actual.iter().zip(expected) {
+            assert!((coord.lat - lat).abs() < 1e-9, "{} != {}", coord.lat, lat);
+            assert!((coord.lon - lon).abs() < 1e-9, "{} != {}", coord.lon, lon);
+        }
+    }
+
+    #[test]
+    fn test_decode_reference_vector() {
+        let (header, coords) = decode_with_header("BFoz5xJ67i1B1B7PzIhaxL7Y").unwrap();
+        assert_eq!(header.precision, 5);
+        assert_eq!(header.third_dimension, ThirdDimension::Absent);
+        assert_close(
+            &coords,
+            &[
+                (50.10228, 8.69821),
+                (50.10201, 8.69567),
+                (50.10063, 8.69150),
+                (50.09878, 8.68752),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_encode_reference_vector() {
+        let coords = vec![
+            Coordinate::new(50.1022829, 8.6982122),
+            Coordinate::new(50.1020076, 8.6956695),
+            Coordinate::new(50.1006313, 8.6914960),
+            Coordinate::new(50.0987800, 8.6875156),
+        ];
+        assert_eq!(
+            encode(&coords, &Header::default()).unwrap(),
+            "BFoz5xJ67i1B1B7PzIhaxL7Y"
+        );
+    }
+
+    #[test]
+    fn test_third_dimension_reference_vector() {
+        let coords = vec![
+            Coordinate::with_elevation(50.1022829, 8.6982122, 10.0),
+            Coordinate::with_elevation(50.1020076, 8.6956695, 20.0),
+            Coordinate::with_elevation(50.1006313, 8.6914960, 30.0),
+            Coordinate::with_elevation(50.0987800, 8.6875156, 40.0),
+        ];
+        let header = Header {
+            precision: 5,
+            third_dimension: ThirdDimension::Altitude,
+            third_dimension_precision: 0,
+        };
+        let encoded = encode(&coords, &header).unwrap();
+        assert_eq!(encoded, "BlBoz5xJ67i1BU1B7PUzIhaUxL7YU");
+
+        let (decoded_header, decoded) = decode_with_header(&encoded).unwrap();
+        assert_eq!(decoded_header, header);
+        assert_eq!(decoded[3].ele, Some(40.0));
+    }
+
+    #[test]
+    fn test_round_trip_high_precision() {
+        let coords = vec![
+            Coordinate::with_elevation(-33.8688197, 151.2092955, 58.25),
+            Coordinate::with_elevation(-33.8567844, 151.2152967, 3.5),
+        ];
+        let header = Header {
+            precision: 7,
+            third_dimension: ThirdDimension::Elevation,
+            third_dimension_precision: 2,
+        };
+        let decoded = decode(&encode(&coords, &header).unwrap()).unwrap();
+        for (a, b) in decoded.iter().zip(&coords) {
+            assert!((a.lat - b.lat).abs() < 1e-7);
+            assert!((a.lon - b.lon).abs() < 1e-7);
+            assert!((a.ele.unwrap() - b.ele.unwrap()).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_decode_unsupported_version() {
+        assert!(matches!(
+            decode("CFoz5xJ67i1B"),
+            Err(FlexPolylineError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn test_decode_invalid_characters() {
+        assert!(matches!(
+            decode("BF!!"),
+            Err(FlexPolylineError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn test_oversized_deltas_do_not_overflow() {
+        let mut encoded = String::new();
+        encode_unsigned(FORMAT_VERSION, &mut encoded);
+        encode_unsigned(Header::default().precision as i64, &mut encoded);
+        for _ in 0..3 {
+            encode_unsigned(zigzag_encode(i64::MAX - 1), &mut encoded);
+            encode_unsigned(zigzag_encode(0), &mut encoded);
+        }
+        assert_eq!(decode(&encoded).unwrap().len(), 3);
+
+        let far = [
+            Coordinate::new(1e300, -1e300),
+            Coordinate::new(-1e300, 1e300),
+        ];
+        assert!(encode(&far, &Header::default()).is_ok());
+    }
+
+    #[test]
+    fn test_encode_invalid_precision() {
+        let header = Header {
+            precision: 16,
+            ..Header::default()
+        };
+        assert!(matches!(
+            encode(&[], &header),
+            Err(FlexPolylineError::InvalidPrecision)
+        ));
+    }
+}
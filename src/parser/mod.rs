@@ -1,4 +1,5 @@
+pub mod fit;
+pub mod flexpolyline;
 pub mod kml;
 pub mod polyline;
 pub mod url;
-
@@ -7,31 +7,145 @@ pub enum PolylineError {
     InvalidEncoding,
     #[error("Unexpected end of input")]
     UnexpectedEnd,
+    #[error("Precision must be between 0 and {MAX_PRECISION}, got {0}")]
+    InvalidPrecision(u32),
 }
 
-pub fn decode(encoded: &str) -> Result<Vec<Coordinate>, PolylineError> {
+/// Precision used by Google Maps polylines (five decimal places).
+pub const DEFAULT_PRECISION: u32 = 5;
+/// Highest precision that still leaves room for every coordinate in an
+/// `i64` after scaling.
+pub const MAX_PRECISION: u32 = 15;
+
+pub fn decode(encoded: &str, precision: u32) -> Result<Vec<Coordinate>, PolylineError> {
+    decode_dimensions(encoded, precision, None)
+}
+
+/// Decodes a polyline carrying elevation as a third value per point.
+pub fn decode_with_elevation(
+    encoded: &str,
+    precision: u32,
+    elevation_precision: u32,
+) -> Result<Vec<Coordinate>, PolylineError> {
+    decode_dimensions(encoded, precision, Some(elevation_precision))
+}
+
+pub fn encode(coordinates: &[Coordinate], precision: u32) -> Result<String, PolylineError> {
+    encode_dimensions(coordinates, precision, None)
+}
+
+/// Encodes elevation as a third value per point; missing elevations are
+/// written as zero.
+pub fn encode_with_elevation(
+    coordinates: &[Coordinate],
+    precision: u32,
+    elevation_precision: u32,
+) -> Result<String, PolylineError> {
+    encode_dimensions(coordinates, precision, Some(elevation_precision))
+}
+
+fn decode_dimensions(
+    encoded: &str,
+    precision: u32,
+    elevation_precision: Option<u32>,
+) -> Result<Vec<Coordinate>, PolylineError> {
+    check_precision(precision, elevation_precision)?;
+    let factor = 10f64.powi(precision as i32);
     let mut coordinates = Vec::new();
     let mut index = 0;
     let mut lat: i64 = 0;
     let mut lng: i64 = 0;
+    let mut ele: i64 = 0;
     let bytes = encoded.as_bytes();
 
     while index < bytes.len() {
         let (delta_lat, new_index) = decode_value(bytes, index)?;
         index = new_index;
-        lat += delta_lat;
+        lat = lat.wrapping_add(delta_lat);
 
         let (delta_lng, new_index) = decode_value(bytes, index)?;
         index = new_index;
-        lng += delta_lng;
+        lng = lng.wrapping_add(delta_lng);
+
+        let coordinate = match elevation_precision {
+            Some(ele_precision) => {
+                let (delta_ele, new_index) = decode_value(bytes, index)?;
+                index = new_index;
+                ele = ele.wrapping_add(delta_ele);
 
-        coordinates.push(Coordinate::new(lat as f64 / 1e5, lng as f64 / 1e5));
+                Coordinate::with_elevation(
+                    lat as f64 / factor,
+                    lng as f64 / factor,
+                    ele as f64 / 10f64.powi(ele_precision as i32),
+                )
+            }
+            None => Coordinate::new(lat as f64 / factor, lng as f64 / factor),
+        };
+
+        coordinates.push(coordinate);
     }
 
     Ok(coordinates)
 }
 
+fn encode_dimensions(
+    coordinates: &[Coordinate],
+    precision: u32,
+    elevation_precision: Option<u32>,
+) -> Result<String, PolylineError> {
+    check_precision(precision, elevation_precision)?;
+    let factor = 10f64.powi(precision as i32);
+    let mut encoded = String::new();
+    let mut prev_lat: i64 = 0;
+    let mut prev_lng: i64 = 0;
+    let mut prev_ele: i64 = 0;
+
+    for coordinate in coordinates {
+        let lat = (coordinate.lat * factor).round() as i64;
+        let lng = (coordinate.lon * factor).round() as i64;
+        encode_value(lat.wrapping_sub(prev_lat), &mut encoded);
+        encode_value(lng.wrapping_sub(prev_lng), &mut encoded);
+        prev_lat = lat;
+        prev_lng = lng;
+
+        if let Some(ele_precision) = elevation_precision {
+            let ele_factor = 10f64.powi(ele_precision as i32);
+            let ele = (coordinate.ele.unwrap_or(0.0) * ele_factor).round() as i64;
+            encode_value(ele.wrapping_sub(prev_ele), &mut encoded);
+            prev_ele = ele;
+        }
+    }
+
+    Ok(encoded)
+}
+
+fn check_precision(precision: u32, elevation_precision: Option<u32>) -> Result<(), PolylineError> {
+    match elevation_precision {
+        Some(p) if p > MAX_PRECISION => Err(PolylineError::InvalidPrecision(p)),
+        _ if precision > MAX_PRECISION => Err(PolylineError::InvalidPrecision(precision)),
+        _ => Ok(()),
+    }
+}
+
+fn encode_value(value: i64, output: &mut String) {
+    encode_varint(zigzag_encode(value), output, |chunk| (chunk + 63) as char);
+}
+
 fn decode_value(bytes: &[u8], start: usize) -> Result<(i64, usize), PolylineError> {
+    let (value, index) = decode_varint(bytes, start, |byte| {
+        (63..=127).contains(&byte).then(|| byte - 63)
+    })?;
+    Ok((zigzag_decode(value), index))
+}
+
+/// Reads one unsigned value made of 5-bit chunks, least significant first,
+/// with `0x20` marking that another chunk follows. `to_chunk` maps an input
+/// byte to its chunk, or `None` if the byte is not part of the alphabet.
+pub(crate) fn decode_varint(
+    bytes: &[u8],
+    start: usize,
+    to_chunk: impl Fn(u8) -> Option<u8>,
+) -> Result<(i64, usize), PolylineError> {
     let mut result: i64 = 0;
     let mut shift = 0;
     let mut index = start;
@@ -40,13 +154,11 @@ fn decode_value(bytes: &[u8], start: usize) -> Result<(i64, usize), PolylineErro
         if index >= bytes.len() {
             return Err(PolylineError::UnexpectedEnd);
         }
-
-        let byte = bytes[index];
-        if byte < 63 || byte > 127 {
+        if shift >= 64 {
             return Err(PolylineError::InvalidEncoding);
         }
 
-        let chunk = (byte - 63) as i64;
+        let chunk = to_chunk(bytes[index]).ok_or(PolylineError::InvalidEncoding)? as i64;
         index += 1;
 
         result |= (chunk & 0x1F) << shift;
@@ -57,13 +169,36 @@ fn decode_value(bytes: &[u8], start: usize) -> Result<(i64, usize), PolylineErro
         }
     }
 
-    let value = if result & 1 != 0 {
-        !(result >> 1)
+    Ok((result, index))
+}
+
+/// Writes an unsigned value in the chunked form read by [`decode_varint`].
+pub(crate) fn encode_varint(value: i64, output: &mut String, to_char: impl Fn(u8) -> char) {
+    // Treat the bits as unsigned so out-of-range values still terminate.
+    let mut remaining = value as u64;
+
+    while remaining >= 0x20 {
+        output.push(to_char(((remaining & 0x1F) | 0x20) as u8));
+        remaining >>= 5;
+    }
+
+    output.push(to_char(remaining as u8));
+}
+
+pub(crate) fn zigzag_encode(value: i64) -> i64 {
+    if value < 0 {
+        !(value << 1)
     } else {
-        result >> 1
-    };
+        value << 1
+    }
+}
 
-    Ok((value, index))
+pub(crate) fn zigzag_decode(value: i64) -> i64 {
+    if value & 1 != 0 {
+        !(value >> 1)
+    } else {
+        value >> 1
+    }
 }
 
 #[cfg(test)]
@@ -73,7 +208,7 @@ mod tests {
     #[test]
     fn test_decode_simple() {
         let encoded = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
-        let coords = decode(encoded).unwrap();
+        let coords = decode(encoded, DEFAULT_PRECISION).unwrap();
         assert_eq!(coords.len(), 3);
         assert!((coords[0].lat - 38.5).abs() < 0.001);
         assert!((coords[0].lon - (-120.2)).abs() < 0.001);
@@ -82,30 +217,117 @@ mod tests {
     #[test]
     fn test_decode_single_point() {
         let encoded = "_p~iF~ps|U";
-        let coords = decode(encoded).unwrap();
+        let coords = decode(encoded, DEFAULT_PRECISION).unwrap();
         assert_eq!(coords.len(), 1);
     }
 
     #[test]
     fn test_decode_empty_string() {
         let encoded = "";
-        let coords = decode(encoded).unwrap();
+        let coords = decode(encoded, DEFAULT_PRECISION).unwrap();
         assert!(coords.is_empty());
     }
 
     #[test]
     fn test_decode_invalid_characters() {
         let encoded = "!!!invalid!!!";
-        let result = decode(encoded);
+        let result = decode(encoded, DEFAULT_PRECISION);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_decode_coordinate_precision() {
         let encoded = "_p~iF~ps|U";
-        let coords = decode(encoded).unwrap();
+        let coords = decode(encoded, DEFAULT_PRECISION).unwrap();
         assert!((coords[0].lat - 38.5).abs() < 0.00001);
         assert!((coords[0].lon - (-120.2)).abs() < 0.00001);
     }
-}
 
+    #[test]
+    fn test_encode_simple() {
+        let coords = vec![
+            Coordinate::new(38.5, -120.2),
+            Coordinate::new(40.7, -120.95),
+            Coordinate::new(43.252, -126.453),
+        ];
+        assert_eq!(
+            encode(&coords, DEFAULT_PRECISION).unwrap(),
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`@"
+        );
+    }
+
+    #[test]
+    fn test_encode_precision_6() {
+        let coords = vec![
+            Coordinate::new(38.5, -120.2),
+            Coordinate::new(40.7, -120.95),
+            Coordinate::new(43.252, -126.453),
+        ];
+        let encoded = encode(&coords, 6).unwrap();
+        assert_eq!(encoded, "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI");
+
+        let decoded = decode(&encoded, 6).unwrap();
+        assert!((decoded[2].lat - 43.252).abs() < 1e-7);
+        assert!((decoded[2].lon - (-126.453)).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_decode_wrong_precision_scales_coordinates() {
+        let coords = decode("_izlhA~rlgdF", DEFAULT_PRECISION).unwrap();
+        assert!((coords[0].lat - 385.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_round_trip_with_elevation() {
+        let coords = vec![
+            Coordinate::with_elevation(37.7749, -122.4194, 12.5),
+            Coordinate::with_elevation(37.7835, -122.4089, 48.0),
+            Coordinate::with_elevation(37.79, -122.4, 3.25),
+        ];
+        let encoded = encode_with_elevation(&coords, 6, 2).unwrap();
+        let decoded = decode_with_elevation(&encoded, 6, 2).unwrap();
+
+        assert_eq!(decoded.len(), 3);
+        for (a, b) in decoded.iter().zip(&coords) {
+            assert!((a.lat - b.lat).abs() < 1e-6);
+            assert!((a.lon - b.lon).abs() < 1e-6);
+            assert!((a.ele.unwrap() - b.ele.unwrap()).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_encode_empty() {
+        assert_eq!(encode(&[], DEFAULT_PRECISION).unwrap(), "");
+    }
+
+    #[test]
+    fn test_rejects_excessive_precision() {
+        let coords = vec![Coordinate::with_elevation(38.5, -120.2, 100.0)];
+        for precision in [16, 19, 30, 400] {
+            assert!(matches!(
+                encode(&coords, precision),
+                Err(PolylineError::InvalidPrecision(p)) if p == precision
+            ));
+            assert!(matches!(
+                decode("_p~iF~ps|U", precision),
+                Err(PolylineError::InvalidPrecision(_))
+            ));
+        }
+        assert!(matches!(
+            encode_with_elevation(&coords, DEFAULT_PRECISION, 19),
+            Err(PolylineError::InvalidPrecision(19))
+        ));
+    }
+
+    #[test]
+    fn test_max_precision_does_not_overflow() {
+        let coords = vec![
+            Coordinate::with_elevation(-90.0, -180.0, -1e6),
+            Coordinate::with_elevation(90.0, 180.0, 1e6),
+        ];
+        let encoded = encode_with_elevation(&coords, MAX_PRECISION, MAX_PRECISION).unwrap();
+        let decoded = decode_with_elevation(&encoded, MAX_PRECISION, MAX_PRECISION).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert!((decoded[1].lon - 180.0).abs() < 1e-9);
+    }
+}
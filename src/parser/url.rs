@@ -1,5 +1,7 @@
 use crate::parser::polyline;
+use crate::transform::datum::{self, Datum, DatumOptions};
 use crate::types::{Coordinate, Route, Track, TrackSegment, Waypoint};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use url::Url;
 
@@ -15,7 +17,19 @@ pub enum UrlParseError {
     DecodeError(String),
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParseOptions {
+    /// Google Maps serves GCJ-02 coordinates inside mainland China; convert
+    /// those points to WGS-84 so they line up on GPS devices.
+    pub correct_china_offset: bool,
+}
+
 pub fn parse(url_str: &str) -> Result<Route, UrlParseError> {
+    parse_with_options(url_str, &ParseOptions::default())
+}
+
+pub fn parse_with_options(url_str: &str, options: &ParseOptions) -> Result<Route, UrlParseError> {
     let url = Url::parse(url_str).map_err(|e| UrlParseError::InvalidUrl(e.to_string()))?;
 
     let host = url.host_str().unwrap_or("");
@@ -45,7 +59,7 @@ pub fn parse(url_str: &str) -> Result<Route, UrlParseError> {
     }
 
     if let Some(encoded_polyline) = extract_polyline_from_data(&url) {
-        if let Ok(coords) = polyline::decode(&encoded_polyline) {
+        if let Ok(coords) = polyline::decode(&encoded_polyline, polyline::DEFAULT_PRECISION) {
             if !coords.is_empty() {
                 let segment = TrackSegment::new(coords);
                 if route.tracks.is_empty() {
@@ -81,6 +95,16 @@ pub fn parse(url_str: &str) -> Result<Route, UrlParseError> {
         return Err(UrlParseError::NoRouteData);
     }
 
+    if options.correct_china_offset {
+        route = datum::convert_route(
+            &route,
+            &DatumOptions {
+                from: Datum::Gcj02,
+                to: Datum::Wgs84,
+            },
+        );
+    }
+
     Ok(route)
 }
 
@@ -167,12 +191,12 @@ fn extract_coordinates_from_data(url: &Url) -> Option<Vec<Coordinate>> {
     let mut current_lon: Option<f64> = None;
 
     for part in &parts {
-        if part.starts_with("1d") {
-            if let Ok(lon) = part[2..].parse::<f64>() {
+        if let Some(value) = part.strip_prefix("1d") {
+            if let Ok(lon) = value.parse::<f64>() {
                 current_lon = Some(lon);
             }
-        } else if part.starts_with("2d") {
-            if let (Some(lon), Ok(lat)) = (current_lon, part[2..].parse::<f64>()) {
+        } else if let Some(value) = part.strip_prefix("2d") {
+            if let (Some(lon), Ok(lat)) = (current_lon, value.parse::<f64>()) {
                 if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) {
                     coordinates.push(Coordinate::new(lat, lon));
                 }
@@ -308,5 +332,29 @@ mod tests {
         assert_eq!(route.waypoints.len(), 2);
         assert_eq!(route.tracks.len(), 1);
     }
-}
 
+    #[test]
+    fn test_correct_china_offset() {
+        let url = "https://www.google.com/maps/dir/39.916404,116.410244/40.0,116.5/";
+        let options = ParseOptions {
+            correct_china_offset: true,
+        };
+        let raw = parse(url).unwrap();
+        let corrected = parse_with_options(url, &options).unwrap();
+
+        let start = &corrected.waypoints[0].coord;
+        assert!((start.lat - 39.915).abs() < 1e-5);
+        assert!((start.lon - 116.404).abs() < 1e-5);
+        assert_eq!(raw.waypoints[0].coord.lat, 39.916404);
+    }
+
+    #[test]
+    fn test_china_offset_skips_points_outside_china() {
+        let url = "https://www.google.com/maps/dir/37.7749,-122.4194/37.7835,-122.4089/";
+        let options = ParseOptions {
+            correct_china_offset: true,
+        };
+        let route = parse_with_options(url, &options).unwrap();
+        assert_eq!(route.waypoints[0].coord.lat, 37.7749);
+    }
+}
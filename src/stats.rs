@@ -0,0 +1,321 @@
+use crate::geometry::distance::{haversine, length, vincenty};
+use crate::types::{Coordinate, Route};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsOptions {
+    /// Elevation changes smaller than this many metres are ignored when
+    /// accumulating gain and loss.
+    pub hysteresis: f64,
+    /// Minimum horizontal distance in metres over which grade is measured.
+    pub grade_window: f64,
+    /// Also compute lengths on the WGS-84 ellipsoid.
+    pub ellipsoidal: bool,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            hysteresis: 5.0,
+            grade_window: 50.0,
+            ellipsoidal: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bounds {
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+}
+
+impl Bounds {
+    fn extend(&mut self, coord: &Coordinate) {
+        self.min_lat = self.min_lat.min(coord.lat);
+        self.min_lon = self.min_lon.min(coord.lon);
+        self.max_lat = self.max_lat.max(coord.lat);
+        self.max_lon = self.max_lon.max(coord.lon);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentStats {
+    pub track_index: usize,
+    pub segment_index: usize,
+    pub points: usize,
+    pub distance: f64,
+    pub distance_ellipsoidal: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ElevationStats {
+    pub gain: f64,
+    pub loss: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Steepest grade as a fraction (0.1 is 10%), positive uphill.
+    pub max_grade: f64,
+    /// Steepest downhill grade as a fraction, negative.
+    pub min_grade: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteStats {
+    pub distance: f64,
+    pub distance_ellipsoidal: Option<f64>,
+    pub bounds: Option<Bounds>,
+    pub waypoint_count: usize,
+    pub track_count: usize,
+    pub segment_count: usize,
+    pub point_count: usize,
+    pub elevation: Option<ElevationStats>,
+    pub segments: Vec<SegmentStats>,
+}
+
+/// Bounding box of every waypoint and track point in the route.
+pub fn bounds(route: &Route) -> Option<Bounds> {
+    let mut coords = route.waypoints.iter().map(|w| &w.coord).chain(
+        route
+            .tracks
+            .iter()
+            .flat_map(|t| t.segments.iter())
+            .flat_map(|s| s.points.iter()),
+    );
+
+    let first = coords.next()?;
+    let mut bounds = Bounds {
+        min_lat: first.lat,
+        min_lon: first.lon,
+        max_lat: first.lat,
+        max_lon: first.lon,
+    };
+    for coord in coords {
+        bounds.extend(coord);
+    }
+    Some(bounds)
+}
+
+pub fn compute(route: &Route, options: &StatsOptions) -> RouteStats {
+    let mut segments = Vec::new();
+    let mut elevation: Option<ElevationStats> = None;
+
+    for (t, track) in route.tracks.iter().enumerate() {
+        for (s, segment) in track.segments.iter().enumerate() {
+            let points = &segment.points;
+            segments.push(SegmentStats {
+                track_index: t,
+                segment_index: s,
+                points: points.len(),
+                distance: length(points),
+                distance_ellipsoidal: options.ellipsoidal.then(|| ellipsoidal_length(points)),
+            });
+
+            if let Some(segment_elevation) = elevation_stats(points, options) {
+                elevation = Some(match elevation {
+                    Some(total) => ElevationStats {
+                        gain: total.gain + segment_elevation.gain,
+                        loss: total.loss + segment_elevation.loss,
+                        min: total.min.min(segment_elevation.min),
+                        max: total.max.max(segment_elevation.max),
+                        max_grade: total.max_grade.max(segment_elevation.max_grade),
+                        min_grade: total.min_grade.min(segment_elevation.min_grade),
+                    },
+                    None => segment_elevation,
+                });
+            }
+        }
+    }
+
+    RouteStats {
+        distance: segments.iter().map(|s| s.distance).sum(),
+        distance_ellipsoidal: options
+            .ellipsoidal
+            .then(|| segments.iter().filter_map(|s| s.distance_ellipsoidal).sum()),
+        bounds: bounds(route),
+        waypoint_count: route.waypoints.len(),
+        track_count: route.tracks.len(),
+        segment_count: segments.len(),
+        point_count: segments.iter().map(|s| s.points).sum(),
+        elevation,
+        segments,
+    }
+}
+
+/// Ellipsoidal length, falling back to haversine for any edge where
+/// Vincenty's formula does not converge.
+fn ellipsoidal_length(points: &[Coordinate]) -> f64 {
+    points
+        .windows(2)
+        .map(|pair| vincenty(&pair[0], &pair[1]).unwrap_or_else(|| haversine(&pair[0], &pair[1])))
+        .sum()
+}
+
+/// Gain, loss, extremes and grades over the points that carry elevation.
+pub fn elevation_stats(points: &[Coordinate], options: &StatsOptions) -> Option<ElevationStats> {
+    let profile: Vec<(f64, f64)> = {
+        let mut distance = 0.0;
+        let mut previous: Option<&Coordinate> = None;
+        let mut profile = Vec::new();
+        for point in points {
+            if let Some(prev) = previous {
+                distance += haversine(prev, point);
+            }
+            previous = Some(point);
+            if let Some(ele) = point.ele {
+                profile.push((distance, ele));
+            }
+        }
+        profile
+    };
+
+    let first = profile.first()?;
+    let (gain, loss) = gain_loss(profile.iter().map(|p| p.1), options.hysteresis);
+    let mut stats = ElevationStats {
+        gain,
+        loss,
+        min: first.1,
+        max: first.1,
+        max_grade: 0.0,
+        min_grade: 0.0,
+    };
+
+    let mut end = 0;
+    for (start, &(distance, ele)) in profile.iter().enumerate() {
+        stats.min = stats.min.min(ele);
+        stats.max = stats.max.max(ele);
+
+        end = end.max(start);
+        while end < profile.len() && profile[end].0 - distance < options.grade_window {
+            end += 1;
+        }
+        if let Some(&(far_distance, far_ele)) = profile.get(end) {
+            let grade = (far_ele - ele) / (far_distance - distance);
+            stats.max_grade = stats.max_grade.max(grade);
+            stats.min_grade = stats.min_grade.min(grade);
+        }
+    }
+
+    Some(stats)
+}
+
+/// Total climb and descent, only counting changes once they exceed `threshold`
+/// from the last accepted elevation.
+pub fn gain_loss(elevations: impl IntoIterator<Item = f64>, threshold: f64) -> (f64, f64) {
+    let mut gain = 0.0;
+    let mut loss = 0.0;
+    let mut reference: Option<f64> = None;
+
+    for ele in elevations {
+        match reference {
+            None => reference = Some(ele),
+            Some(r) => {
+                let delta = ele - r;
+                if delta.abs() >= threshold {
+                    if delta > 0.0 {
+                        gain += delta;
+                    } else {
+                        loss -= delta;
+                    }
+                    reference = Some(ele);
+                }
+            }
+        }
+    }
+
+    (gain, loss)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Track, TrackSegment, Waypoint};
+
+    fn hill() -> Route {
+        let mut route = Route::new();
+        let elevations = [100.0, 102.0, 110.0, 120.0, 118.0, 121.0, 100.0];
+        let points = elevations
+            .iter()
+            .enumerate()
+            .map(|(i, &ele)| Coordinate::with_elevation(0.0, i as f64 * 0.001, ele))
+            .collect();
+        route.add_track(Track::new(vec![TrackSegment::new(points)]));
+        route
+    }
+
+    #[test]
+    fn test_bounds_include_waypoints() {
+        let mut route = hill();
+        route.add_waypoint(Waypoint::new(Coordinate::new(1.0, -1.0)));
+        let bounds = bounds(&route).unwrap();
+        assert_eq!(bounds.min_lat, 0.0);
+        assert_eq!(bounds.max_lat, 1.0);
+        assert_eq!(bounds.min_lon, -1.0);
+        assert!((bounds.max_lon - 0.006).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_bounds_empty_route() {
+        assert!(bounds(&Route::new()).is_none());
+    }
+
+    #[test]
+    fn test_distance_and_counts() {
+        let stats = compute(&hill(), &StatsOptions::default());
+        assert_eq!(stats.point_count, 7);
+        assert_eq!(stats.segment_count, 1);
+        assert!((stats.distance - 6.0 * 111.195).abs() < 0.5);
+        assert!(stats.distance_ellipsoidal.is_none());
+    }
+
+    #[test]
+    fn test_ellipsoidal_distance() {
+        let options = StatsOptions {
+            ellipsoidal: true,
+            ..StatsOptions::default()
+        };
+        let stats = compute(&hill(), &options);
+        let ellipsoidal = stats.distance_ellipsoidal.unwrap();
+        assert!((ellipsoidal - stats.distance).abs() / stats.distance < 0.01);
+    }
+
+    #[test]
+    fn test_elevation_with_hysteresis() {
+        let stats = compute(&hill(), &StatsOptions::default());
+        let elevation = stats.elevation.unwrap();
+        assert_eq!(elevation.min, 100.0);
+        assert_eq!(elevation.max, 121.0);
+        assert_eq!(elevation.gain, 20.0);
+        assert_eq!(elevation.loss, 20.0);
+    }
+
+    #[test]
+    fn test_elevation_without_hysteresis() {
+        let (gain, loss) = gain_loss([100.0, 102.0, 110.0, 120.0, 118.0, 121.0, 100.0], 0.0);
+        assert_eq!(gain, 23.0);
+        assert_eq!(loss, 23.0);
+    }
+
+    #[test]
+    fn test_max_grade() {
+        let elevation = compute(&hill(), &StatsOptions::default())
+            .elevation
+            .unwrap();
+        assert!((elevation.max_grade - 10.0 / 111.195).abs() < 1e-3);
+        assert!((elevation.min_grade - (-21.0 / 111.195)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_no_elevation() {
+        let mut route = Route::new();
+        route.add_track(Track::new(vec![TrackSegment::new(vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 1.0),
+        ])]));
+        assert!(compute(&route, &StatsOptions::default())
+            .elevation
+            .is_none());
+    }
+}
@@ -0,0 +1,371 @@
+use crate::geometry::distance::cumulative_segments;
+use crate::geometry::project::project;
+use crate::time::format_iso8601;
+use crate::transform::timestamps::ensure_timestamps;
+use crate::types::{Coordinate, Route, Track, Waypoint};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::Writer;
+use std::io::Cursor;
+use thiserror::Error;
+
+// Limits imposed by the TCX v2 schema on course and course point names.
+const MAX_COURSE_NAME_LEN: usize = 15;
+const MAX_COURSE_POINT_NAME_LEN: usize = 10;
+
+#[derive(Error, Debug)]
+pub enum TcxError {
+    #[error("XML writing error: {0}")]
+    XmlError(String),
+    #[error("IO error: {0}")]
+    IoError(String),
+}
+
+impl From<quick_xml::Error> for TcxError {
+    fn from(e: quick_xml::Error) -> Self {
+        TcxError::XmlError(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for TcxError {
+    fn from(e: std::io::Error) -> Self {
+        TcxError::IoError(e.to_string())
+    }
+}
+
+/// A track flattened into a single course line with cumulative distances,
+/// which do not count the gaps between segments, and a time on every point.
+struct CourseLine<'a> {
+    name: String,
+    points: Vec<Coordinate>,
+    distances: Vec<f64>,
+    course_points: Vec<&'a Waypoint>,
+}
+
+pub fn write(route: &Route) -> Result<String, TcxError> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+
+    let mut root = BytesStart::new("TrainingCenterDatabase");
+    root.push_attribute((
+        "xmlns",
+        "http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2",
+    ));
+    root.push_attribute(("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"));
+    root.push_attribute((
+        "xsi:schemaLocation",
+        "http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2 http://www.garmin.com/xmlschemas/TrainingCenterDatabasev2.xsd",
+    ));
+    writer.write_event(Event::Start(root))?;
+    writer.write_event(Event::Start(BytesStart::new("Courses")))?;
+
+    // TCX requires a time on every trackpoint and course point.
+    let route = ensure_timestamps(route);
+    for course in course_lines(&route) {
+        write_course(&mut writer, &course)?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("Courses")))?;
+    writer.write_event(Event::End(BytesEnd::new("TrainingCenterDatabase")))?;
+
+    let result = writer.into_inner().into_inner();
+    String::from_utf8(result).map_err(|e| TcxError::XmlError(e.to_string()))
+}
+
+fn course_lines(route: &Route) -> Vec<CourseLine<'_>> {
+    let base_name = route.name.as_deref().unwrap_or("Route");
+    let tracks: Vec<&Track> = route
+        .tracks
+        .iter()
+        .filter(|track| track.segments.iter().any(|s| !s.points.is_empty()))
+        .collect();
+
+    let mut courses: Vec<CourseLine> = tracks
+        .iter()
+        .enumerate()
+        .map(|(i, track)| {
+            let points: Vec<Coordinate> = track
+                .segments
+                .iter()
+                .flat_map(|segment| segment.points.iter().cloned())
+                .collect();
+            let name = match (&track.name, tracks.len()) {
+                (Some(name), _) => name.clone(),
+                (None, 1) => base_name.to_string(),
+                (None, _) => format!("{} {}", base_name, i + 1),
+            };
+            CourseLine {
+                name,
+                distances: cumulative_segments(&track.segments),
+                points,
+                course_points: Vec::new(),
+            }
+        })
+        .collect();
+
+    // Each waypoint becomes a course point on whichever course passes closest.
+    for waypoint in &route.waypoints {
+        let nearest = courses
+            .iter()
+            .enumerate()
+            .filter_map(|(i, course)| {
+                project(&course.points, &waypoint.coord).map(|p| (i, p.distance_off))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((i, _)) = nearest {
+            courses[i].course_points.push(waypoint);
+        }
+    }
+
+    courses
+}
+
+fn write_course<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    course: &CourseLine,
+) -> Result<(), TcxError> {
+    writer.write_event(Event::Start(BytesStart::new("Course")))?;
+    write_text_element(writer, "Name", &truncate(&course.name, MAX_COURSE_NAME_LEN))?;
+
+    let total = course.distances.last().copied().unwrap_or(0.0);
+    let first = &course.points[0];
+    let last = &course.points[course.points.len() - 1];
+    let duration = match (first.time, last.time) {
+        (Some(start), Some(end)) => (end - start) as f64 / 1000.0,
+        _ => 0.0,
+    };
+
+    writer.write_event(Event::Start(BytesStart::new("Lap")))?;
+    write_text_element(writer, "TotalTimeSeconds", &duration.to_string())?;
+    write_text_element(writer, "DistanceMeters", &format_distance(total))?;
+    write_position(writer, "BeginPosition", first)?;
+    write_position(writer, "EndPosition", last)?;
+    write_text_element(writer, "Intensity", "Active")?;
+    writer.write_event(Event::End(BytesEnd::new("Lap")))?;
+
+    writer.write_event(Event::Start(BytesStart::new("Track")))?;
+    for (point, distance) in course.points.iter().zip(&course.distances) {
+        writer.write_event(Event::Start(BytesStart::new("Trackpoint")))?;
+        write_time(writer, point)?;
+        write_position(writer, "Position", point)?;
+        if let Some(ele) = point.ele {
+            write_text_element(writer, "AltitudeMeters", &ele.to_string())?;
+        }
+        write_text_element(writer, "DistanceMeters", &format_distance(*distance))?;
+        writer.write_event(Event::End(BytesEnd::new("Trackpoint")))?;
+    }
+    writer.write_event(Event::End(BytesEnd::new("Track")))?;
+
+    let mut snapped: Vec<(f64, &Waypoint, Coordinate)> = course
+        .course_points
+        .iter()
+        .filter_map(|waypoint| {
+            project(&course.points, &waypoint.coord).map(|p| (p.distance_along, *waypoint, p.point))
+        })
+        .collect();
+    snapped.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (_, waypoint, point) in snapped {
+        writer.write_event(Event::Start(BytesStart::new("CoursePoint")))?;
+        let name = waypoint.name.as_deref().unwrap_or("Point");
+        write_text_element(writer, "Name", &truncate(name, MAX_COURSE_POINT_NAME_LEN))?;
+        write_time(writer, &point)?;
+        write_position(writer, "Position", &point)?;
+        if let Some(ele) = point.ele.or(waypoint.coord.ele) {
+            write_text_element(writer, "AltitudeMeters", &ele.to_string())?;
+        }
+        write_text_element(writer, "PointType", "Generic")?;
+        if let Some(ref name) = waypoint.name {
+            write_text_element(writer, "Notes", name)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("CoursePoint")))?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("Course")))?;
+
+    Ok(())
+}
+
+fn write_time<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    coord: &Coordinate,
+) -> Result<(), TcxError> {
+    if let Some(time) = coord.time {
+        write_text_element(writer, "Time", &format_iso8601(time))?;
+    }
+    Ok(())
+}
+
+fn write_position<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    element: &str,
+    coord: &Coordinate,
+) -> Result<(), TcxError> {
+    writer.write_event(Event::Start(BytesStart::new(element)))?;
+    write_text_element(writer, "LatitudeDegrees", &coord.lat.to_string())?;
+    write_text_element(writer, "LongitudeDegrees", &coord.lon.to_string())?;
+    writer.write_event(Event::End(BytesEnd::new(element)))?;
+    Ok(())
+}
+
+fn write_text_element<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    name: &str,
+    text: &str,
+) -> Result<(), TcxError> {
+    writer.write_event(Event::Start(BytesStart::new(name)))?;
+    writer.write_event(Event::Text(BytesText::new(text)))?;
+    writer.write_event(Event::End(BytesEnd::new(name)))?;
+    Ok(())
+}
+
+fn format_distance(metres: f64) -> String {
+    format!("{:.2}", metres)
+}
+
+fn truncate(name: &str, max: usize) -> String {
+    name.chars().take(max).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::distance::haversine;
+    use crate::types::TrackSegment;
+
+    fn sample_route() -> Route {
+        let mut route = Route::with_name("Morning Loop".to_string());
+        let segment = TrackSegment::new(vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 0.01),
+            Coordinate::new(0.0, 0.02),
+        ]);
+        route.add_track(Track::new(vec![segment]));
+        route
+    }
+
+    #[test]
+    fn test_write_tcx_header() {
+        let tcx = write(&Route::new()).unwrap();
+        assert!(tcx.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(
+            tcx.contains("xmlns=\"http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2\"")
+        );
+        assert!(tcx.contains("<Courses></Courses>"));
+    }
+
+    #[test]
+    fn test_write_course_with_cumulative_distance() {
+        let tcx = write(&sample_route()).unwrap();
+        assert!(tcx.contains("<Name>Morning Loop</Name>"));
+        assert_eq!(tcx.matches("<Trackpoint>").count(), 3);
+        assert!(tcx.contains("<DistanceMeters>0.00</DistanceMeters>"));
+
+        let step = haversine(&Coordinate::new(0.0, 0.0), &Coordinate::new(0.0, 0.01));
+        assert!(tcx.contains(&format!(
+            "<DistanceMeters>{}</DistanceMeters>",
+            format_distance(step)
+        )));
+        assert!(tcx.contains(&format!(
+            "<DistanceMeters>{}</DistanceMeters>",
+            format_distance(step * 2.0)
+        )));
+    }
+
+    #[test]
+    fn test_course_points_snap_to_track_in_order() {
+        let mut route = sample_route();
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.001, 0.015),
+            "Cafe".to_string(),
+        ));
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(-0.001, 0.005),
+            "Water".to_string(),
+        ));
+
+        let tcx = write(&route).unwrap();
+        assert_eq!(tcx.matches("<CoursePoint>").count(), 2);
+        let water = tcx.find("<Name>Water</Name>").unwrap();
+        let cafe = tcx.find("<Name>Cafe</Name>").unwrap();
+        assert!(water < cafe);
+        assert!(tcx.contains("<LatitudeDegrees>0</LatitudeDegrees><LongitudeDegrees>0.015"));
+    }
+
+    #[test]
+    fn test_truncates_names_to_schema_limits() {
+        let mut route = sample_route();
+        route.name = Some("A very long course name".to_string());
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, 0.0),
+            "Starting line".to_string(),
+        ));
+
+        let tcx = write(&route).unwrap();
+        assert!(tcx.contains("<Name>A very long cou</Name>"));
+        assert!(tcx.contains("<Name>Starting l</Name>"));
+        assert!(tcx.contains("<Notes>Starting line</Notes>"));
+    }
+
+    #[test]
+    fn test_writes_synthetic_times() {
+        let mut route = sample_route();
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, 0.01),
+            "Turn".to_string(),
+        ));
+
+        let tcx = write(&route).unwrap();
+        assert_eq!(tcx.matches("<Time>").count(), 4);
+        assert!(tcx.contains("<Trackpoint><Time>2000-01-01T00:00:00Z</Time>"));
+        assert!(!tcx.contains("<TotalTimeSeconds>0</TotalTimeSeconds>"));
+
+        let name = tcx.find("<Name>Turn</Name>").unwrap();
+        let time = tcx[name..].find("<Time>").unwrap();
+        let position = tcx[name..].find("<Position>").unwrap();
+        assert!(time < position);
+    }
+
+    #[test]
+    fn test_uses_recorded_times() {
+        let mut route = sample_route();
+        for (i, point) in route.tracks[0].segments[0].points.iter_mut().enumerate() {
+            point.time = Some(1_714_552_200_000 + i as i64 * 90_000);
+        }
+
+        let tcx = write(&route).unwrap();
+        assert!(tcx.contains("<Time>2024-05-01T08:30:00Z</Time>"));
+        assert!(tcx.contains("<Time>2024-05-01T08:33:00Z</Time>"));
+        assert!(tcx.contains("<TotalTimeSeconds>180</TotalTimeSeconds>"));
+    }
+
+    #[test]
+    fn test_gaps_between_segments_do_not_add_distance() {
+        let mut route = Route::new();
+        route.add_track(Track::new(vec![
+            TrackSegment::new(vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01)]),
+            TrackSegment::new(vec![Coordinate::new(1.0, 0.0), Coordinate::new(1.0, 0.01)]),
+        ]));
+
+        let tcx = write(&route).unwrap();
+        let total = haversine(&Coordinate::new(0.0, 0.0), &Coordinate::new(0.0, 0.01))
+            + haversine(&Coordinate::new(1.0, 0.0), &Coordinate::new(1.0, 0.01));
+        assert!(tcx.contains(&format!(
+            "<DistanceMeters>{}</DistanceMeters><BeginPosition>",
+            format_distance(total)
+        )));
+    }
+
+    #[test]
+    fn test_one_course_per_track() {
+        let mut route = sample_route();
+        let segment =
+            TrackSegment::new(vec![Coordinate::new(1.0, 1.0), Coordinate::new(1.0, 1.01)]);
+        route.add_track(Track::new(vec![segment]));
+
+        let tcx = write(&route).unwrap();
+        assert_eq!(tcx.matches("<Course>").count(), 2);
+        assert!(tcx.contains("<Name>Morning Loop 2</Name>"));
+    }
+}
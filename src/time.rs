@@ -0,0 +1,67 @@
+/// Formats milliseconds since the Unix epoch as an ISO-8601 UTC timestamp,
+/// e.g. `2024-05-01T08:30:00Z`. Milliseconds are included only when non-zero.
+pub fn format_iso8601(millis: i64) -> String {
+    let seconds = millis.div_euclid(1000);
+    let fraction = millis.rem_euclid(1000);
+    let days = seconds.div_euclid(86_400);
+    let of_day = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    let mut formatted = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        of_day / 3600,
+        of_day % 3600 / 60,
+        of_day % 60
+    );
+    if fraction != 0 {
+        formatted.push_str(&format!(".{:03}", fraction));
+    }
+    formatted.push('Z');
+    formatted
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian date, using
+/// Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_epoch() {
+        assert_eq!(format_iso8601(0), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_format_with_millis() {
+        assert_eq!(
+            format_iso8601(1_714_552_200_250),
+            "2024-05-01T08:30:00.250Z"
+        );
+    }
+
+    #[test]
+    fn test_format_leap_day() {
+        assert_eq!(format_iso8601(951_782_400_000), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_format_before_epoch() {
+        assert_eq!(format_iso8601(-1000), "1969-12-31T23:59:59Z");
+    }
+}
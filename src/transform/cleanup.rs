@@ -0,0 +1,295 @@
+use crate::diagnostics::Diagnostics;
+use crate::types::{Coordinate, Route};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CleanupOptions {
+    /// Remove consecutive track points at the same position.
+    pub remove_duplicates: bool,
+    /// Swap latitude and longitude when the data can only make sense that way.
+    pub fix_swapped_axes: bool,
+    /// Drop points whose latitude or longitude is out of range, rather than
+    /// only reporting them.
+    pub drop_out_of_range: bool,
+}
+
+impl Default for CleanupOptions {
+    fn default() -> Self {
+        Self {
+            remove_duplicates: true,
+            fix_swapped_axes: true,
+            drop_out_of_range: true,
+        }
+    }
+}
+
+/// Validates and repairs route geometry, reporting every change. Points with
+/// NaN or infinite coordinates are always dropped, as are segments left
+/// with fewer than two distinct points and tracks left with no segments.
+pub fn cleanup(route: &Route, options: &CleanupOptions) -> (Route, Diagnostics) {
+    let mut cleaned = route.clone();
+    let mut diagnostics = Diagnostics::new();
+
+    let mut non_finite = 0;
+    let mut invalid_elevation = 0;
+    retain_coordinates(&mut cleaned, |coord| {
+        if !coord.lat.is_finite() || !coord.lon.is_finite() {
+            non_finite += 1;
+            return false;
+        }
+        if coord.ele.is_some_and(|e| !e.is_finite()) {
+            coord.ele = None;
+            invalid_elevation += 1;
+        }
+        true
+    });
+    if non_finite > 0 {
+        diagnostics.warning(
+            "non_finite_coordinate",
+            format!(
+                "Removed {} points with NaN or infinite coordinates",
+                non_finite
+            ),
+        );
+    }
+    if invalid_elevation > 0 {
+        diagnostics.warning(
+            "non_finite_elevation",
+            format!("Cleared {} NaN or infinite elevations", invalid_elevation),
+        );
+    }
+
+    if options.fix_swapped_axes && axes_look_swapped(&cleaned) {
+        retain_coordinates(&mut cleaned, |coord| {
+            std::mem::swap(&mut coord.lat, &mut coord.lon);
+            true
+        });
+        diagnostics.warning(
+            "swapped_axes",
+            "Latitudes exceed 90° while every longitude is a valid latitude; swapped latitude and longitude"
+                .to_string(),
+        );
+    }
+
+    let mut out_of_range = 0;
+    retain_coordinates(&mut cleaned, |coord| {
+        let valid = coord.lat.abs() <= 90.0 && coord.lon.abs() <= 180.0;
+        if !valid {
+            out_of_range += 1;
+        }
+        valid || !options.drop_out_of_range
+    });
+    if out_of_range > 0 {
+        let action = if options.drop_out_of_range {
+            "Removed"
+        } else {
+            "Found"
+        };
+        diagnostics.warning(
+            "out_of_range_coordinate",
+            format!(
+                "{} {} points with latitude or longitude out of range",
+                action, out_of_range
+            ),
+        );
+    }
+
+    if options.remove_duplicates {
+        let mut duplicates = 0;
+        for segment in cleaned
+            .tracks
+            .iter_mut()
+            .flat_map(|t| t.segments.iter_mut())
+        {
+            let before = segment.points.len();
+            segment
+                .points
+                .dedup_by(|b, a| a.lat == b.lat && a.lon == b.lon);
+            duplicates += before - segment.points.len();
+        }
+        if duplicates > 0 {
+            diagnostics.info(
+                "duplicate_points",
+                format!("Removed {} consecutive duplicate points", duplicates),
+            );
+        }
+    }
+
+    let mut degenerate = 0;
+    for track in &mut cleaned.tracks {
+        let before = track.segments.len();
+        track.segments.retain(|s| {
+            s.points
+                .iter()
+                .any(|p| p.lat != s.points[0].lat || p.lon != s.points[0].lon)
+        });
+        degenerate += before - track.segments.len();
+    }
+    if degenerate > 0 {
+        diagnostics.warning(
+            "degenerate_segment",
+            format!("Removed {} segments with zero length", degenerate),
+        );
+    }
+
+    let before = cleaned.tracks.len();
+    cleaned.tracks.retain(|t| !t.segments.is_empty());
+    if cleaned.tracks.len() < before {
+        diagnostics.info(
+            "empty_track",
+            format!(
+                "Removed {} tracks left empty",
+                before - cleaned.tracks.len()
+            ),
+        );
+    }
+
+    (cleaned, diagnostics)
+}
+
+/// Visits every waypoint and track point, dropping those for which `keep`
+/// returns false.
+fn retain_coordinates(route: &mut Route, mut keep: impl FnMut(&mut Coordinate) -> bool) {
+    route.waypoints.retain_mut(|w| keep(&mut w.coord));
+    for segment in route.tracks.iter_mut().flat_map(|t| t.segments.iter_mut()) {
+        segment.points.retain_mut(&mut keep);
+    }
+}
+
+/// Latitude and longitude are taken as swapped when some latitude is
+/// impossible but every longitude would be a valid latitude.
+fn axes_look_swapped(route: &Route) -> bool {
+    let mut coords = route.waypoints.iter().map(|w| &w.coord).chain(
+        route
+            .tracks
+            .iter()
+            .flat_map(|t| &t.segments)
+            .flat_map(|s| &s.points),
+    );
+    let mut impossible = false;
+    let all_fit = coords.all(|c| {
+        impossible |= c.lat.abs() > 90.0;
+        c.lon.abs() <= 90.0 && c.lat.abs() <= 180.0
+    });
+    impossible && all_fit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Track, TrackSegment, Waypoint};
+
+    fn route(points: Vec<Coordinate>) -> Route {
+        let mut route = Route::new();
+        route.add_track(Track::new(vec![TrackSegment::new(points)]));
+        route
+    }
+
+    fn codes(diagnostics: &Diagnostics) -> Vec<&str> {
+        diagnostics
+            .entries
+            .iter()
+            .map(|d| d.code.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_removes_consecutive_duplicates() {
+        let (cleaned, diagnostics) = cleanup(
+            &route(vec![
+                Coordinate::new(1.0, 2.0),
+                Coordinate::new(1.0, 2.0),
+                Coordinate::new(1.5, 2.0),
+                Coordinate::new(1.0, 2.0),
+            ]),
+            &CleanupOptions::default(),
+        );
+        assert_eq!(cleaned.tracks[0].segments[0].points.len(), 3);
+        assert_eq!(codes(&diagnostics), vec!["duplicate_points"]);
+        assert!(diagnostics.entries[0].message.contains("1 consecutive"));
+    }
+
+    #[test]
+    fn test_drops_non_finite_values() {
+        let mut bad_ele = Coordinate::new(1.5, 2.0);
+        bad_ele.ele = Some(f64::NAN);
+        let (cleaned, diagnostics) = cleanup(
+            &route(vec![
+                Coordinate::new(1.0, 2.0),
+                Coordinate::new(f64::NAN, 2.0),
+                bad_ele,
+                Coordinate::new(1.0, f64::INFINITY),
+            ]),
+            &CleanupOptions::default(),
+        );
+        let points = &cleaned.tracks[0].segments[0].points;
+        assert_eq!(points.len(), 2);
+        assert!(points[1].ele.is_none());
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["non_finite_coordinate", "non_finite_elevation"]
+        );
+    }
+
+    #[test]
+    fn test_swaps_axes() {
+        let mut original = route(vec![
+            Coordinate::new(-122.4194, 37.7749),
+            Coordinate::new(-122.4089, 37.7835),
+        ]);
+        original.add_waypoint(Waypoint::new(Coordinate::new(-122.4, 37.79)));
+
+        let (cleaned, diagnostics) = cleanup(&original, &CleanupOptions::default());
+        assert_eq!(cleaned.tracks[0].segments[0].points[0].lat, 37.7749);
+        assert_eq!(cleaned.waypoints[0].coord.lon, -122.4);
+        assert_eq!(codes(&diagnostics), vec!["swapped_axes"]);
+    }
+
+    #[test]
+    fn test_does_not_swap_valid_data() {
+        let (cleaned, diagnostics) = cleanup(
+            &route(vec![
+                Coordinate::new(37.7, -122.4),
+                Coordinate::new(37.8, -122.4),
+            ]),
+            &CleanupOptions::default(),
+        );
+        assert_eq!(cleaned.tracks[0].segments[0].points[0].lat, 37.7);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let original = route(vec![
+            Coordinate::new(10.0, 200.0),
+            Coordinate::new(10.0, 20.0),
+            Coordinate::new(11.0, 20.0),
+        ]);
+
+        let (cleaned, diagnostics) = cleanup(&original, &CleanupOptions::default());
+        assert_eq!(cleaned.tracks[0].segments[0].points.len(), 2);
+        assert_eq!(codes(&diagnostics), vec!["out_of_range_coordinate"]);
+
+        let options = CleanupOptions {
+            drop_out_of_range: false,
+            ..Default::default()
+        };
+        let (kept, diagnostics) = cleanup(&original, &options);
+        assert_eq!(kept.tracks[0].segments[0].points.len(), 3);
+        assert!(diagnostics.entries[0].message.starts_with("Found 1"));
+    }
+
+    #[test]
+    fn test_removes_zero_length_segments_and_empty_tracks() {
+        let (cleaned, diagnostics) = cleanup(
+            &route(vec![Coordinate::new(1.0, 2.0), Coordinate::new(1.0, 2.0)]),
+            &CleanupOptions::default(),
+        );
+        assert!(cleaned.tracks.is_empty());
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["duplicate_points", "degenerate_segment", "empty_track"]
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use crate::geometry::project::interpolate;
+use crate::stats::Bounds;
+use crate::types::{Coordinate, Route, TrackSegment};
+
+/// An area that lines can be clipped against.
+pub trait Region {
+    fn contains(&self, point: &Coordinate) -> bool;
+
+    /// Fractions along the edge from `a` to `b`, strictly between 0 and 1,
+    /// where the edge crosses the region's boundary.
+    fn crossings(&self, a: &Coordinate, b: &Coordinate) -> Vec<f64>;
+}
+
+/// A polygon ring in longitude/latitude, closed implicitly. Edges are
+/// treated as straight lines on a plate carrée map, which is fine for the
+/// park- or city-sized areas routes are clipped to.
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    pub ring: Vec<Coordinate>,
+}
+
+impl Polygon {
+    pub fn new(ring: Vec<Coordinate>) -> Self {
+        Self { ring }
+    }
+
+    pub fn from_bounds(bounds: &Bounds) -> Self {
+        Self::new(vec![
+            Coordinate::new(bounds.min_lat, bounds.min_lon),
+            Coordinate::new(bounds.min_lat, bounds.max_lon),
+            Coordinate::new(bounds.max_lat, bounds.max_lon),
+            Coordinate::new(bounds.max_lat, bounds.min_lon),
+        ])
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (&Coordinate, &Coordinate)> {
+        self.ring
+            .iter()
+            .zip(self.ring.iter().cycle().skip(1))
+            .take(self.ring.len())
+    }
+}
+
+impl Region for Polygon {
+    fn contains(&self, point: &Coordinate) -> bool {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            if (a.lat > point.lat) != (b.lat > point.lat) {
+                let lon = a.lon + (point.lat - a.lat) / (b.lat - a.lat) * (b.lon - a.lon);
+                if point.lon < lon {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    fn crossings(&self, a: &Coordinate, b: &Coordinate) -> Vec<f64> {
+        let (dx, dy) = (b.lon - a.lon, b.lat - a.lat);
+        self.edges()
+            .filter_map(|(p, q)| {
+                let (ex, ey) = (q.lon - p.lon, q.lat - p.lat);
+                let denominator = dx * ey - dy * ex;
+                if denominator == 0.0 {
+                    return None;
+                }
+                let (fx, fy) = (p.lon - a.lon, p.lat - a.lat);
+                let t = (fx * ey - fy * ex) / denominator;
+                let u = (fx * dy - fy * dx) / denominator;
+                ((0.0..=1.0).contains(&u) && t > 0.0 && t < 1.0).then_some(t)
+            })
+            .collect()
+    }
+}
+
+/// Splits a segment into the runs that lie inside `region`, or outside it
+/// when `keep_inside` is false. Each run is cut exactly at the boundary.
+pub fn clip_segment(
+    segment: &TrackSegment,
+    region: &dyn Region,
+    keep_inside: bool,
+) -> Vec<TrackSegment> {
+    let points = &segment.points;
+    let mut runs = Vec::new();
+    let mut current: Vec<Coordinate> = Vec::new();
+
+    if points.len() == 1 && region.contains(&points[0]) == keep_inside {
+        return vec![segment.clone()];
+    }
+
+    for pair in points.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        let mut fractions = vec![0.0];
+        let mut crossings = region.crossings(a, b);
+        crossings.sort_by(|x, y| x.total_cmp(y));
+        fractions.extend(crossings);
+        fractions.push(1.0);
+
+        for span in fractions.windows(2) {
+            let (from, to) = (span[0], span[1]);
+            if to <= from {
+                continue;
+            }
+            let middle = interpolate(a, b, (from + to) / 2.0);
+            if region.contains(&middle) == keep_inside {
+                if current.is_empty() {
+                    current.push(at(a, b, from));
+                }
+                current.push(at(a, b, to));
+            } else if !current.is_empty() {
+                runs.push(TrackSegment::new(std::mem::take(&mut current)));
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        runs.push(TrackSegment::new(current));
+    }
+    runs
+}
+
+fn at(a: &Coordinate, b: &Coordinate, fraction: f64) -> Coordinate {
+    match fraction {
+        f if f <= 0.0 => a.clone(),
+        f if f >= 1.0 => b.clone(),
+        f => interpolate(a, b, f),
+    }
+}
+
+/// Keeps only the parts of the route inside `region`. Tracks leaving and
+/// re-entering the region are split into several segments, tracks entirely
+/// outside are dropped, and so are waypoints outside.
+pub fn clip_route(route: &Route, region: &dyn Region) -> Route {
+    let mut clipped = route.clone();
+
+    clipped.waypoints.retain(|w| region.contains(&w.coord));
+    for track in &mut clipped.tracks {
+        track.segments = track
+            .segments
+            .iter()
+            .flat_map(|s| clip_segment(s, region, true))
+            .collect();
+    }
+    clipped.tracks.retain(|t| !t.segments.is_empty());
+
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Track, Waypoint};
+
+    fn square() -> Polygon {
+        Polygon::from_bounds(&Bounds {
+            min_lat: 0.0,
+            min_lon: 0.0,
+            max_lat: 1.0,
+            max_lon: 1.0,
+        })
+    }
+
+    fn line(lons: &[f64]) -> TrackSegment {
+        TrackSegment::new(lons.iter().map(|&lon| Coordinate::new(0.5, lon)).collect())
+    }
+
+    fn lons(segment: &TrackSegment) -> Vec<f64> {
+        segment.points.iter().map(|p| p.lon).collect()
+    }
+
+    #[test]
+    fn test_contains() {
+        let square = square();
+        assert!(square.contains(&Coordinate::new(0.5, 0.5)));
+        assert!(!square.contains(&Coordinate::new(1.5, 0.5)));
+        assert!(!square.contains(&Coordinate::new(0.5, -0.1)));
+    }
+
+    #[test]
+    fn test_clip_cuts_at_boundary() {
+        let runs = clip_segment(&line(&[-1.0, 0.5, 2.0]), &square(), true);
+        assert_eq!(runs.len(), 1);
+        let lons = lons(&runs[0]);
+        assert_eq!(lons.len(), 3);
+        assert!(lons[0].abs() < 1e-12);
+        assert_eq!(lons[1], 0.5);
+        assert!((lons[2] - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_clip_splits_on_exit_and_reentry() {
+        // A U-shaped polygon: the line leaves through the notch and comes back.
+        let u = Polygon::new(vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 3.0),
+            Coordinate::new(1.0, 3.0),
+            Coordinate::new(1.0, 2.0),
+            Coordinate::new(0.2, 2.0),
+            Coordinate::new(0.2, 1.0),
+            Coordinate::new(1.0, 1.0),
+            Coordinate::new(1.0, 0.0),
+        ]);
+        let runs = clip_segment(&line(&[0.5, 2.5]), &u, true);
+        assert_eq!(runs.len(), 2);
+        assert!((lons(&runs[0])[1] - 1.0).abs() < 1e-12);
+        assert!((lons(&runs[1])[0] - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_clip_outside() {
+        let runs = clip_segment(&line(&[-1.0, 0.5, 2.0]), &square(), false);
+        assert_eq!(runs.len(), 2);
+        assert!((lons(&runs[1])[0] - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_clip_route_drops_outside_parts() {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::new(Coordinate::new(0.5, 0.5)));
+        route.add_waypoint(Waypoint::new(Coordinate::new(5.0, 5.0)));
+        route.add_track(Track::new(vec![line(&[0.2, 0.8])]));
+        route.add_track(Track::new(vec![line(&[3.0, 4.0])]));
+
+        let clipped = clip_route(&route, &square());
+        assert_eq!(clipped.waypoints.len(), 1);
+        assert_eq!(clipped.tracks.len(), 1);
+        assert_eq!(lons(&clipped.tracks[0].segments[0]), vec![0.2, 0.8]);
+    }
+}
@@ -0,0 +1,288 @@
+use super::clip::{Polygon, Region};
+use crate::types::{Coordinate, Route};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+
+/// Coordinate systems used by maps in mainland China. GCJ-02 is the
+/// obfuscated datum mandated for Chinese map services, including Google Maps
+/// within China; BD-09 is Baidu's further offset of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Datum {
+    #[default]
+    Wgs84,
+    Gcj02,
+    Bd09,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatumOptions {
+    pub from: Datum,
+    pub to: Datum,
+}
+
+impl Default for DatumOptions {
+    fn default() -> Self {
+        Self {
+            from: Datum::Gcj02,
+            to: Datum::Wgs84,
+        }
+    }
+}
+
+/// Krasovsky 1940 ellipsoid, which the GCJ-02 offsets are defined against.
+const KRASOVSKY_A: f64 = 6_378_245.0;
+const KRASOVSKY_EE: f64 = 0.006_693_421_622_965_943;
+const BD_X_PI: f64 = PI * 3000.0 / 180.0;
+
+/// Coarse outline of mainland China and Hainan as (lat, lon), including
+/// coastal waters and excluding Taiwan, Hong Kong and Macau. It is only
+/// accurate to a few tens of kilometres along land borders.
+const CHINA_BOUNDARY: &[(f64, f64)] = &[
+    (53.6, 121.2),
+    (53.4, 125.0),
+    (50.2, 127.6),
+    (48.3, 134.7),
+    (45.0, 133.1),
+    (42.4, 130.7),
+    (41.0, 126.0),
+    (39.8, 124.2),
+    (37.4, 122.9),
+    (31.0, 122.9),
+    (27.0, 121.2),
+    (26.5, 120.5),
+    (25.0, 119.3),
+    (23.5, 117.5),
+    (22.5, 114.45),
+    (22.52, 114.05),
+    (22.45, 113.93),
+    (22.15, 113.82),
+    (22.1, 113.61),
+    (22.22, 113.61),
+    (22.22, 113.53),
+    (22.1, 113.53),
+    (21.4, 111.8),
+    (18.0, 111.2),
+    (17.9, 108.5),
+    (21.5, 108.0),
+    (22.8, 106.6),
+    (22.9, 104.0),
+    (21.1, 101.2),
+    (22.0, 99.2),
+    (24.0, 97.6),
+    (28.2, 97.4),
+    (27.8, 92.0),
+    (28.2, 85.9),
+    (30.2, 81.3),
+    (35.5, 77.9),
+    (37.0, 74.9),
+    (39.4, 73.5),
+    (42.2, 80.2),
+    (47.2, 83.0),
+    (49.2, 87.3),
+    (45.3, 90.8),
+    (42.7, 96.4),
+    (42.3, 101.7),
+    (41.6, 105.0),
+    (43.5, 111.8),
+    (45.1, 113.6),
+    (46.5, 119.9),
+    (49.6, 117.4),
+    (50.3, 119.3),
+];
+
+/// Whether a point lies inside mainland China, where GCJ-02 applies.
+pub fn in_china(coord: &Coordinate) -> bool {
+    static CHINA: OnceLock<Polygon> = OnceLock::new();
+    CHINA
+        .get_or_init(|| {
+            Polygon::new(
+                CHINA_BOUNDARY
+                    .iter()
+                    .map(|&(lat, lon)| Coordinate::new(lat, lon))
+                    .collect(),
+            )
+        })
+        .contains(coord)
+}
+
+/// GCJ-02 offset in degrees of latitude and longitude at a WGS-84 point.
+fn gcj02_offset(lat: f64, lon: f64) -> (f64, f64) {
+    let (x, y) = (lon - 105.0, lat - 35.0);
+    let common = (20.0 * (6.0 * x * PI).sin() + 20.0 * (2.0 * x * PI).sin()) * 2.0 / 3.0;
+
+    let dlat = -100.0
+        + 2.0 * x
+        + 3.0 * y
+        + 0.2 * y * y
+        + 0.1 * x * y
+        + 0.2 * x.abs().sqrt()
+        + common
+        + (20.0 * (y * PI).sin() + 40.0 * (y / 3.0 * PI).sin()) * 2.0 / 3.0
+        + (160.0 * (y / 12.0 * PI).sin() + 320.0 * (y * PI / 30.0).sin()) * 2.0 / 3.0;
+    let dlon = 300.0
+        + x
+        + 2.0 * y
+        + 0.1 * x * x
+        + 0.1 * x * y
+        + 0.1 * x.abs().sqrt()
+        + common
+        + (20.0 * (x * PI).sin() + 40.0 * (x / 3.0 * PI).sin()) * 2.0 / 3.0
+        + (150.0 * (x / 12.0 * PI).sin() + 300.0 * (x / 30.0 * PI).sin()) * 2.0 / 3.0;
+
+    let rad_lat = lat.to_radians();
+    let magic = 1.0 - KRASOVSKY_EE * rad_lat.sin().powi(2);
+    let sqrt_magic = magic.sqrt();
+    (
+        dlat * 180.0 / (KRASOVSKY_A * (1.0 - KRASOVSKY_EE) / (magic * sqrt_magic) * PI),
+        dlon * 180.0 / (KRASOVSKY_A / sqrt_magic * rad_lat.cos() * PI),
+    )
+}
+
+fn moved(coord: &Coordinate, lat: f64, lon: f64) -> Coordinate {
+    Coordinate {
+        lat,
+        lon,
+        ..coord.clone()
+    }
+}
+
+pub fn wgs84_to_gcj02(coord: &Coordinate) -> Coordinate {
+    let (dlat, dlon) = gcj02_offset(coord.lat, coord.lon);
+    moved(coord, coord.lat + dlat, coord.lon + dlon)
+}
+
+/// Inverts [`wgs84_to_gcj02`] by fixed-point iteration, to well under a
+/// millimetre.
+pub fn gcj02_to_wgs84(coord: &Coordinate) -> Coordinate {
+    let (mut lat, mut lon) = (coord.lat, coord.lon);
+    for _ in 0..10 {
+        let (dlat, dlon) = gcj02_offset(lat, lon);
+        let (next_lat, next_lon) = (coord.lat - dlat, coord.lon - dlon);
+        let converged = (next_lat - lat).abs() < 1e-10 && (next_lon - lon).abs() < 1e-10;
+        (lat, lon) = (next_lat, next_lon);
+        if converged {
+            break;
+        }
+    }
+    moved(coord, lat, lon)
+}
+
+pub fn gcj02_to_bd09(coord: &Coordinate) -> Coordinate {
+    let (x, y) = (coord.lon, coord.lat);
+    let z = (x * x + y * y).sqrt() + 0.000_02 * (y * BD_X_PI).sin();
+    let theta = y.atan2(x) + 0.000_003 * (x * BD_X_PI).cos();
+    moved(coord, z * theta.sin() + 0.006, z * theta.cos() + 0.0065)
+}
+
+pub fn bd09_to_gcj02(coord: &Coordinate) -> Coordinate {
+    let (x, y) = (coord.lon - 0.0065, coord.lat - 0.006);
+    let z = (x * x + y * y).sqrt() - 0.000_02 * (y * BD_X_PI).sin();
+    let theta = y.atan2(x) - 0.000_003 * (x * BD_X_PI).cos();
+    moved(coord, z * theta.sin(), z * theta.cos())
+}
+
+/// Converts a point between datums, going through GCJ-02 where needed.
+/// Points outside mainland China are returned unchanged, as Chinese map
+/// services do not offset them.
+pub fn convert(coord: &Coordinate, from: Datum, to: Datum) -> Coordinate {
+    if from == to || !in_china(coord) {
+        return coord.clone();
+    }
+
+    let gcj02 = match from {
+        Datum::Wgs84 => wgs84_to_gcj02(coord),
+        Datum::Gcj02 => coord.clone(),
+        Datum::Bd09 => bd09_to_gcj02(coord),
+    };
+    match to {
+        Datum::Wgs84 => gcj02_to_wgs84(&gcj02),
+        Datum::Gcj02 => gcj02,
+        Datum::Bd09 => gcj02_to_bd09(&gcj02),
+    }
+}
+
+/// Converts every waypoint and track point of a route between datums.
+pub fn convert_route(route: &Route, options: &DatumOptions) -> Route {
+    let mut converted = route.clone();
+    let points = converted.waypoints.iter_mut().map(|w| &mut w.coord).chain(
+        converted
+            .tracks
+            .iter_mut()
+            .flat_map(|t| &mut t.segments)
+            .flat_map(|s| &mut s.points),
+    );
+    for point in points {
+        *point = convert(point, options.from, options.to);
+    }
+    converted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::distance::haversine;
+
+    fn beijing() -> Coordinate {
+        Coordinate::with_elevation(39.915, 116.404, 44.0)
+    }
+
+    #[test]
+    fn test_in_china() {
+        assert!(in_china(&beijing()));
+        assert!(in_china(&Coordinate::new(43.825, 87.617))); // Ürümqi
+        assert!(in_china(&Coordinate::new(18.253, 109.512))); // Sanya
+        assert!(!in_china(&Coordinate::new(25.033, 121.565))); // Taipei
+        assert!(in_china(&Coordinate::new(22.543, 114.058))); // Shenzhen
+        assert!(in_china(&Coordinate::new(22.271, 113.577))); // Zhuhai
+        assert!(!in_china(&Coordinate::new(22.28, 114.16))); // Hong Kong
+        assert!(!in_china(&Coordinate::new(22.19, 113.55))); // Macau
+        assert!(!in_china(&Coordinate::new(35.68, 139.69))); // Tokyo
+        assert!(!in_china(&Coordinate::new(47.92, 106.92))); // Ulaanbaatar
+    }
+
+    #[test]
+    fn test_wgs84_to_gcj02_known_point() {
+        let gcj02 = wgs84_to_gcj02(&beijing());
+        assert!((gcj02.lat - 39.916_404).abs() < 1e-5);
+        assert!((gcj02.lon - 116.410_244).abs() < 1e-5);
+        assert_eq!(gcj02.ele, Some(44.0));
+    }
+
+    #[test]
+    fn test_gcj02_round_trip() {
+        let back = gcj02_to_wgs84(&wgs84_to_gcj02(&beijing()));
+        assert!(haversine(&back, &beijing()) < 0.001);
+    }
+
+    #[test]
+    fn test_bd09_round_trip() {
+        let gcj02 = wgs84_to_gcj02(&beijing());
+        let bd09 = gcj02_to_bd09(&gcj02);
+        assert!(haversine(&bd09, &gcj02) > 500.0);
+        assert!(haversine(&bd09_to_gcj02(&bd09), &gcj02) < 0.5);
+    }
+
+    #[test]
+    fn test_convert_leaves_points_outside_china() {
+        let tokyo = Coordinate::new(35.68, 139.69);
+        let converted = convert(&tokyo, Datum::Gcj02, Datum::Wgs84);
+        assert_eq!((converted.lat, converted.lon), (tokyo.lat, tokyo.lon));
+    }
+
+    #[test]
+    fn test_convert_route() {
+        use crate::types::{Track, TrackSegment, Waypoint};
+
+        let mut route = Route::new();
+        let gcj02 = wgs84_to_gcj02(&beijing());
+        route.add_waypoint(Waypoint::new(gcj02.clone()));
+        route.add_track(Track::new(vec![TrackSegment::new(vec![gcj02])]));
+
+        let converted = convert_route(&route, &DatumOptions::default());
+        assert!(haversine(&converted.waypoints[0].coord, &beijing()) < 0.001);
+        assert!(haversine(&converted.tracks[0].segments[0].points[0], &beijing()) < 0.001);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::geometry::distance::haversine;
+use crate::types::{Coordinate, Route, Track, TrackSegment};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConcatOptions {
+    /// Join all tracks into a single track, connecting consecutive segments
+    /// into one where the gap between them allows.
+    pub bridge_gaps: bool,
+    /// Largest gap in metres that is bridged. Segments further apart stay
+    /// separate segments of the joined track. No limit when unset.
+    pub max_gap: Option<f64>,
+}
+
+/// Joins several routes end to end. Waypoints and tracks keep their order,
+/// and the name is built from the names of the parts.
+pub fn concatenate(routes: &[Route], options: &ConcatOptions) -> Route {
+    let mut joined = Route::new();
+
+    let names: Vec<&str> = routes.iter().filter_map(|r| r.name.as_deref()).collect();
+    if !names.is_empty() {
+        joined.name = Some(names.join(" + "));
+    }
+
+    for route in routes {
+        joined.waypoints.extend(route.waypoints.iter().cloned());
+        joined.tracks.extend(route.tracks.iter().cloned());
+    }
+
+    if options.bridge_gaps {
+        let segments: Vec<TrackSegment> = joined
+            .tracks
+            .drain(..)
+            .flat_map(|t| t.segments)
+            .filter(|s| !s.points.is_empty())
+            .collect();
+        let mut track = Track::new(bridge(segments, options.max_gap.unwrap_or(f64::INFINITY)));
+        track.name = joined.name.clone();
+        joined.tracks.push(track);
+    }
+
+    joined
+}
+
+/// Chains tracks whose end lies within `tolerance` metres of another track's
+/// start. The touching segments are joined into one, so a route split into
+/// pieces becomes a single continuous track again.
+pub fn merge_tracks(route: &Route, tolerance: f64) -> Route {
+    let mut merged = route.clone();
+
+    while let Some((i, j)) = find_touching(&merged.tracks, tolerance) {
+        let next = merged.tracks.remove(j);
+        let i = if j < i { i - 1 } else { i };
+        let track = &mut merged.tracks[i];
+
+        // The end of one track touches the start of the next, so their
+        // outermost non-empty segments become one.
+        let mut next_segments = next
+            .segments
+            .into_iter()
+            .skip_while(|s| s.points.is_empty());
+        if let (Some(last), Some(first)) = (
+            track
+                .segments
+                .iter_mut()
+                .rev()
+                .find(|s| !s.points.is_empty()),
+            next_segments.next(),
+        ) {
+            append(last, first);
+        }
+        track.segments.extend(next_segments);
+        if track.name.is_none() {
+            track.name = next.name;
+        }
+    }
+
+    merged
+}
+
+fn find_touching(tracks: &[Track], tolerance: f64) -> Option<(usize, usize)> {
+    for (i, a) in tracks.iter().enumerate() {
+        let Some(end) = last_point(a) else { continue };
+        for (j, b) in tracks.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            if let Some(start) = first_point(b) {
+                if haversine(end, start) <= tolerance {
+                    return Some((i, j));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn first_point(track: &Track) -> Option<&Coordinate> {
+    track.segments.iter().find_map(|s| s.points.first())
+}
+
+fn last_point(track: &Track) -> Option<&Coordinate> {
+    track.segments.iter().rev().find_map(|s| s.points.last())
+}
+
+/// Joins consecutive segments whose gap is at most `max_gap` metres.
+fn bridge(segments: Vec<TrackSegment>, max_gap: f64) -> Vec<TrackSegment> {
+    let mut bridged: Vec<TrackSegment> = Vec::new();
+
+    for segment in segments {
+        match bridged.last_mut() {
+            Some(previous) if gap(previous, &segment).is_some_and(|g| g <= max_gap) => {
+                append(previous, segment);
+            }
+            _ => bridged.push(segment),
+        }
+    }
+
+    bridged
+}
+
+fn gap(a: &TrackSegment, b: &TrackSegment) -> Option<f64> {
+    Some(haversine(a.points.last()?, b.points.first()?))
+}
+
+/// Appends `next` to `segment`, dropping its first point when it duplicates
+/// the join.
+fn append(segment: &mut TrackSegment, next: TrackSegment) {
+    let mut points = next.points.into_iter().peekable();
+    if let (Some(last), Some(first)) = (segment.points.last(), points.peek()) {
+        if last.lat == first.lat && last.lon == first.lon {
+            points.next();
+        }
+    }
+    segment.points.extend(points);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Waypoint;
+
+    fn route(name: &str, lons: &[f64]) -> Route {
+        let mut route = Route::with_name(name.to_string());
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, lons[0]),
+            name.to_string(),
+        ));
+        route.add_track(Track::new(vec![TrackSegment::new(
+            lons.iter().map(|&lon| Coordinate::new(0.0, lon)).collect(),
+        )]));
+        route
+    }
+
+    fn point_counts(route: &Route) -> Vec<Vec<usize>> {
+        route
+            .tracks
+            .iter()
+            .map(|t| t.segments.iter().map(|s| s.points.len()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_concatenate_keeps_parts() {
+        let joined = concatenate(
+            &[route("A", &[0.0, 0.01]), route("B", &[0.01, 0.02])],
+            &ConcatOptions::default(),
+        );
+        assert_eq!(joined.name.as_deref(), Some("A + B"));
+        assert_eq!(joined.waypoints.len(), 2);
+        assert_eq!(point_counts(&joined), vec![vec![2], vec![2]]);
+    }
+
+    #[test]
+    fn test_concatenate_bridges_gaps() {
+        let options = ConcatOptions {
+            bridge_gaps: true,
+            max_gap: None,
+        };
+        let joined = concatenate(
+            &[
+                route("A", &[0.0, 0.01]),
+                route("B", &[0.01, 0.02]),
+                route("C", &[0.5, 0.6]),
+            ],
+            &options,
+        );
+        // The duplicate join point between A and B is dropped.
+        assert_eq!(point_counts(&joined), vec![vec![5]]);
+        assert_eq!(joined.tracks[0].name.as_deref(), Some("A + B + C"));
+    }
+
+    #[test]
+    fn test_concatenate_respects_max_gap() {
+        let options = ConcatOptions {
+            bridge_gaps: true,
+            max_gap: Some(100.0),
+        };
+        let joined = concatenate(
+            &[
+                route("A", &[0.0, 0.01]),
+                route("B", &[0.01, 0.02]),
+                route("C", &[0.5, 0.6]),
+            ],
+            &options,
+        );
+        assert_eq!(point_counts(&joined), vec![vec![3, 2]]);
+    }
+
+    #[test]
+    fn test_merge_touching_tracks() {
+        let mut base = route("A", &[0.02, 0.03]);
+        base.tracks.extend(route("B", &[0.0, 0.01]).tracks);
+        base.tracks.extend(route("C", &[0.010_001, 0.02]).tracks);
+        base.tracks.extend(route("D", &[1.0, 1.1]).tracks);
+
+        let merged = merge_tracks(&base, 1.0);
+        assert_eq!(point_counts(&merged), vec![vec![5], vec![2]]);
+        let lons: Vec<f64> = merged.tracks[0].segments[0]
+            .points
+            .iter()
+            .map(|p| p.lon)
+            .collect();
+        assert_eq!(lons, vec![0.0, 0.01, 0.010_001, 0.02, 0.03]);
+    }
+
+    #[test]
+    fn test_merge_ignores_distant_tracks() {
+        let mut base = route("A", &[0.0, 0.01]);
+        base.tracks.extend(route("B", &[0.02, 0.03]).tracks);
+        assert_eq!(
+            point_counts(&merge_tracks(&base, 10.0)),
+            vec![vec![2], vec![2]]
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use crate::geometry::distance::{cumulative, point_at};
+use crate::types::{Route, Track, Waypoint};
+use serde::{Deserialize, Serialize};
+
+const METRES_PER_MILE: f64 = 1609.344;
+
+/// Smallest spacing between markers, in metres. Closer intervals are raised
+/// to this so the number of markers stays bounded.
+pub const MIN_SPACING: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceUnit {
+    #[default]
+    Kilometers,
+    Miles,
+}
+
+impl DistanceUnit {
+    pub fn metres(self) -> f64 {
+        match self {
+            DistanceUnit::Kilometers => 1000.0,
+            DistanceUnit::Miles => METRES_PER_MILE,
+        }
+    }
+
+    pub fn abbreviation(self) -> &'static str {
+        match self {
+            DistanceUnit::Kilometers => "km",
+            DistanceUnit::Miles => "mi",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkerOptions {
+    /// Spacing between markers, in `unit`s.
+    pub interval: f64,
+    pub unit: DistanceUnit,
+    /// Label placed before the distance; defaults to the unit abbreviation.
+    pub prefix: Option<String>,
+    /// Distance, in `unit`s, already covered at the start of each track.
+    /// Markers keep falling on whole multiples of `interval` counted from
+    /// this offset, e.g. a track starting at km 12.5 gets "km 13" first.
+    pub start_offset: f64,
+}
+
+impl Default for MarkerOptions {
+    fn default() -> Self {
+        Self {
+            interval: 1.0,
+            unit: DistanceUnit::Kilometers,
+            prefix: None,
+            start_offset: 0.0,
+        }
+    }
+}
+
+/// Returns a copy of the route with distance markers appended to its
+/// waypoints.
+pub fn add_markers(route: &Route, options: &MarkerOptions) -> Route {
+    let mut marked = route.clone();
+    for track in &route.tracks {
+        marked.waypoints.extend(markers(track, options));
+    }
+    marked
+}
+
+/// Distance markers along one track. Its segments are measured end to end,
+/// without counting any gap between them.
+pub fn markers(track: &Track, options: &MarkerOptions) -> Vec<Waypoint> {
+    let mut waypoints = Vec::new();
+    if options.interval <= 0.0 || !options.interval.is_finite() {
+        return waypoints;
+    }
+
+    let unit = options.unit.metres();
+    let spacing = (options.interval * unit).max(MIN_SPACING);
+    let start = options.start_offset * unit;
+    let prefix = options
+        .prefix
+        .as_deref()
+        .unwrap_or(options.unit.abbreviation());
+
+    // Index of the first marker past the start offset.
+    let mut n = (start / spacing).floor() as i64 + 1;
+    let mut offset = start;
+
+    for segment in &track.segments {
+        if segment.points.is_empty() {
+            continue;
+        }
+        let distances = cumulative(&segment.points);
+        let end = offset + distances[distances.len() - 1];
+
+        while n as f64 * spacing <= end + 1e-6 {
+            let at = n as f64 * spacing;
+            let value = (at / unit * 1e6).round() / 1e6;
+            let mut waypoint = Waypoint::with_name(
+                point_at(&segment.points, &distances, at - offset),
+                format!("{} {}", prefix, value),
+            );
+            waypoint.kind = Some("distance_marker".to_string());
+            waypoints.push(waypoint);
+            n += 1;
+        }
+
+        offset = end;
+    }
+
+    waypoints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::distance::haversine;
+    use crate::types::{Coordinate, TrackSegment};
+
+    // About 5.56 km heading north, as two segments.
+    fn track() -> Track {
+        Track::new(vec![
+            TrackSegment::new(vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.02, 0.0)]),
+            TrackSegment::new(vec![Coordinate::new(0.02, 0.0), Coordinate::new(0.05, 0.0)]),
+        ])
+    }
+
+    fn names(waypoints: &[Waypoint]) -> Vec<&str> {
+        waypoints
+            .iter()
+            .map(|w| w.name.as_deref().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_kilometre_markers() {
+        let markers = markers(&track(), &MarkerOptions::default());
+        assert_eq!(
+            names(&markers),
+            vec!["km 1", "km 2", "km 3", "km 4", "km 5"]
+        );
+
+        let origin = Coordinate::new(0.0, 0.0);
+        for (i, marker) in markers.iter().enumerate() {
+            let along = haversine(&origin, &marker.coord);
+            assert!((along - (i + 1) as f64 * 1000.0).abs() < 0.01);
+        }
+        assert_eq!(markers[0].kind.as_deref(), Some("distance_marker"));
+    }
+
+    #[test]
+    fn test_mile_markers_with_prefix() {
+        let options = MarkerOptions {
+            unit: DistanceUnit::Miles,
+            prefix: Some("Mile".to_string()),
+            ..Default::default()
+        };
+        let markers = markers(&track(), &options);
+        assert_eq!(names(&markers), vec!["Mile 1", "Mile 2", "Mile 3"]);
+    }
+
+    #[test]
+    fn test_fractional_interval() {
+        let options = MarkerOptions {
+            interval: 2.5,
+            ..Default::default()
+        };
+        assert_eq!(names(&markers(&track(), &options)), vec!["km 2.5", "km 5"]);
+    }
+
+    #[test]
+    fn test_start_offset() {
+        let options = MarkerOptions {
+            start_offset: 12.5,
+            ..Default::default()
+        };
+        let markers = markers(&track(), &options);
+        assert_eq!(names(&markers)[0], "km 13");
+        assert_eq!(markers.len(), 6);
+
+        let along = haversine(&Coordinate::new(0.0, 0.0), &markers[0].coord);
+        assert!((along - 500.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_add_markers_keeps_existing_waypoints() {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::new(Coordinate::new(0.0, 0.0)));
+        route.add_track(track());
+        let marked = add_markers(&route, &MarkerOptions::default());
+        assert_eq!(marked.waypoints.len(), 6);
+    }
+
+    #[test]
+    fn test_tiny_interval_is_clamped() {
+        let options = MarkerOptions {
+            interval: 1e-9,
+            ..Default::default()
+        };
+        let markers = markers(&track(), &options);
+        let total = haversine(&Coordinate::new(0.0, 0.0), &Coordinate::new(0.05, 0.0));
+        assert_eq!(markers.len(), (total / MIN_SPACING) as usize);
+        assert_eq!(names(&markers)[0], "km 0.01");
+    }
+
+    #[test]
+    fn test_invalid_interval() {
+        let options = MarkerOptions {
+            interval: 0.0,
+            ..Default::default()
+        };
+        assert!(markers(&track(), &options).is_empty());
+    }
+}
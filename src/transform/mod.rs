@@ -0,0 +1,11 @@
+pub mod cleanup;
+pub mod clip;
+pub mod datum;
+pub mod join;
+pub mod markers;
+pub mod outliers;
+pub mod privacy;
+pub mod reverse;
+pub mod slice;
+pub mod split;
+pub mod timestamps;
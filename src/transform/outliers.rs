@@ -0,0 +1,254 @@
+use crate::geometry::distance::{bearing, haversine};
+use crate::types::{Coordinate, Route, TrackSegment};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutlierOptions {
+    /// A point is a jump when the detour through it is this many times
+    /// longer than going straight from its predecessor to its successor.
+    pub max_distance_ratio: f64,
+    /// Detours shorter than this many metres are never treated as jumps.
+    pub min_jump: f64,
+    /// Highest plausible speed in metres per second, checked when both
+    /// neighbouring points carry timestamps.
+    pub max_speed: Option<f64>,
+    /// A point where the track turns back on itself by more than
+    /// `180 - spike_angle` degrees is an out-and-back spike.
+    pub spike_angle: f64,
+    /// Spikes are only removed when both legs are longer than this many
+    /// metres, so genuine hairpins made of short legs survive.
+    pub min_spike_length: f64,
+}
+
+impl Default for OutlierOptions {
+    fn default() -> Self {
+        Self {
+            max_distance_ratio: 5.0,
+            min_jump: 100.0,
+            max_speed: Some(70.0),
+            spike_angle: 15.0,
+            min_spike_length: 20.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutlierReport {
+    pub jumps: usize,
+    pub speed: usize,
+    pub spikes: usize,
+}
+
+impl OutlierReport {
+    pub fn total(&self) -> usize {
+        self.jumps + self.speed + self.spikes
+    }
+}
+
+enum Outlier {
+    Jump,
+    Speed,
+    Spike,
+}
+
+/// Jumps and spikes need both legs to be this many times longer than the
+/// spacing of the points around them, so the turnaround of a genuine
+/// out-and-back route is kept.
+const MIN_LEG_SPACING_RATIO: f64 = 1.5;
+
+pub fn filter_route(route: &Route, options: &OutlierOptions) -> (Route, OutlierReport) {
+    let mut filtered = route.clone();
+    let mut report = OutlierReport::default();
+
+    for segment in filtered
+        .tracks
+        .iter_mut()
+        .flat_map(|t| t.segments.iter_mut())
+    {
+        let (cleaned, segment_report) = filter_segment(segment, options);
+        *segment = cleaned;
+        report.jumps += segment_report.jumps;
+        report.speed += segment_report.speed;
+        report.spikes += segment_report.spikes;
+    }
+
+    (filtered, report)
+}
+
+/// Removes single points that jump away from the track and straight back.
+/// Each point is compared with the last point kept and the point after it,
+/// and with the spacing of the points around them. The first and last points
+/// are always kept.
+pub fn filter_segment(
+    segment: &TrackSegment,
+    options: &OutlierOptions,
+) -> (TrackSegment, OutlierReport) {
+    let points = &segment.points;
+    let mut report = OutlierReport::default();
+    if points.len() < 3 {
+        return (segment.clone(), report);
+    }
+
+    let mut kept: Vec<Coordinate> = vec![points[0].clone()];
+    for i in 1..points.len() - 1 {
+        let previous = &kept[kept.len() - 1];
+        let before = kept
+            .len()
+            .checked_sub(2)
+            .map(|j| haversine(&kept[j], previous));
+        let after = points.get(i + 2).map(|p| haversine(&points[i + 1], p));
+        let spacing = before.into_iter().chain(after).fold(0.0, f64::max);
+        match classify(previous, &points[i], &points[i + 1], spacing, options) {
+            Some(Outlier::Jump) => report.jumps += 1,
+            Some(Outlier::Speed) => report.speed += 1,
+            Some(Outlier::Spike) => report.spikes += 1,
+            None => kept.push(points[i].clone()),
+        }
+    }
+    kept.push(points[points.len() - 1].clone());
+
+    (TrackSegment::new(kept), report)
+}
+
+fn classify(
+    previous: &Coordinate,
+    point: &Coordinate,
+    next: &Coordinate,
+    spacing: f64,
+    options: &OutlierOptions,
+) -> Option<Outlier> {
+    let inbound = haversine(previous, point);
+    let outbound = haversine(point, next);
+    let direct = haversine(previous, next);
+    let detour = inbound + outbound;
+    let stands_out = inbound.min(outbound) > MIN_LEG_SPACING_RATIO * spacing;
+
+    if stands_out
+        && detour - direct > options.min_jump
+        && detour > options.max_distance_ratio * direct
+    {
+        return Some(Outlier::Jump);
+    }
+
+    if let Some(max_speed) = options.max_speed {
+        let too_fast = |a: &Coordinate, b: &Coordinate, distance: f64| match (a.time, b.time) {
+            (Some(t0), Some(t1)) if t1 > t0 => distance / ((t1 - t0) as f64 / 1000.0) > max_speed,
+            _ => false,
+        };
+        if too_fast(previous, point, inbound) && too_fast(point, next, outbound) {
+            return Some(Outlier::Speed);
+        }
+    }
+
+    if stands_out && inbound > options.min_spike_length && outbound > options.min_spike_length {
+        let turn = (bearing(point, previous) - bearing(point, next)).abs();
+        let angle = turn.min(360.0 - turn);
+        if angle < options.spike_angle {
+            return Some(Outlier::Spike);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Points ~111 m apart heading east, with any overrides applied.
+    fn segment(overrides: &[(usize, f64, f64)]) -> TrackSegment {
+        let mut points: Vec<Coordinate> = (0..6)
+            .map(|i| Coordinate::new(0.0, i as f64 * 0.001))
+            .collect();
+        for &(i, lat, lon) in overrides {
+            points[i] = Coordinate::new(lat, lon);
+        }
+        TrackSegment::new(points)
+    }
+
+    #[test]
+    fn test_removes_jump() {
+        let (filtered, report) =
+            filter_segment(&segment(&[(3, 0.05, 0.003)]), &OutlierOptions::default());
+        assert_eq!(filtered.points.len(), 5);
+        assert_eq!(report.total(), 1);
+        assert!(filtered.points.iter().all(|p| p.lat == 0.0));
+    }
+
+    #[test]
+    fn test_keeps_normal_corner() {
+        let segment = TrackSegment::new(vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 0.001),
+            Coordinate::new(0.001, 0.001),
+            Coordinate::new(0.002, 0.001),
+        ]);
+        let (filtered, report) = filter_segment(&segment, &OutlierOptions::default());
+        assert_eq!(filtered.points.len(), 4);
+        assert_eq!(report, OutlierReport::default());
+    }
+
+    #[test]
+    fn test_removes_out_and_back_spike() {
+        // Overshoots ahead and comes back: too short a detour for a jump.
+        let (filtered, report) =
+            filter_segment(&segment(&[(3, 0.00001, 0.006)]), &OutlierOptions::default());
+        assert_eq!(report.spikes, 1);
+        assert_eq!(filtered.points.len(), 5);
+    }
+
+    #[test]
+    fn test_keeps_out_and_back_turnaround() {
+        // 1.1 km out and back along the same points.
+        let points = (0..21)
+            .map(|i: i32| Coordinate::new(0.0, (10 - (10 - i).abs()) as f64 * 0.001))
+            .collect();
+        let (filtered, report) =
+            filter_segment(&TrackSegment::new(points), &OutlierOptions::default());
+        assert_eq!(report, OutlierReport::default());
+        assert_eq!(filtered.points.len(), 21);
+        assert!((filtered.points[10].lon - 0.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_removes_speed_outlier() {
+        let mut segment = segment(&[(3, 0.0, 0.009)]);
+        for (i, point) in segment.points.iter_mut().enumerate() {
+            point.time = Some(i as i64 * 10_000);
+        }
+        let options = OutlierOptions {
+            max_distance_ratio: 100.0,
+            max_speed: Some(20.0),
+            ..Default::default()
+        };
+        let (filtered, report) = filter_segment(&segment, &options);
+        assert_eq!(report.speed, 1);
+        assert_eq!(filtered.points.len(), 5);
+    }
+
+    #[test]
+    fn test_speed_ignored_without_timestamps() {
+        let options = OutlierOptions {
+            max_distance_ratio: 100.0,
+            max_speed: Some(0.1),
+            spike_angle: 0.0,
+            ..Default::default()
+        };
+        let (_, report) = filter_segment(&segment(&[]), &options);
+        assert_eq!(report.total(), 0);
+    }
+
+    #[test]
+    fn test_filter_route() {
+        let mut route = Route::new();
+        route.add_track(crate::types::Track::new(vec![
+            segment(&[(2, 0.05, 0.002)]),
+            segment(&[]),
+        ]));
+        let (filtered, report) = filter_route(&route, &OutlierOptions::default());
+        assert_eq!(report.jumps, 1);
+        assert_eq!(filtered.tracks[0].segments[0].points.len(), 5);
+        assert_eq!(filtered.tracks[0].segments[1].points.len(), 6);
+    }
+}
@@ -0,0 +1,234 @@
+use super::clip::{clip_segment, Region};
+use crate::geometry::distance::{haversine, EARTH_RADIUS_M};
+use crate::types::{Coordinate, Route};
+use serde::{Deserialize, Serialize};
+
+/// A circle around a sensitive location.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyZone {
+    pub lat: f64,
+    pub lon: f64,
+    /// Radius in metres.
+    pub radius: f64,
+}
+
+impl PrivacyZone {
+    fn centre(&self) -> Coordinate {
+        Coordinate::new(self.lat, self.lon)
+    }
+}
+
+impl Region for PrivacyZone {
+    fn contains(&self, point: &Coordinate) -> bool {
+        haversine(&self.centre(), point) <= self.radius
+    }
+
+    fn crossings(&self, a: &Coordinate, b: &Coordinate) -> Vec<f64> {
+        // Solve |a + t(b - a) - centre| = radius in a local metric frame.
+        let scale = self.lat.to_radians().cos();
+        let to_local = |c: &Coordinate| {
+            (
+                (c.lon - self.lon).to_radians() * scale * EARTH_RADIUS_M,
+                (c.lat - self.lat).to_radians() * EARTH_RADIUS_M,
+            )
+        };
+        let (ax, ay) = to_local(a);
+        let (bx, by) = to_local(b);
+        let (dx, dy) = (bx - ax, by - ay);
+
+        let qa = dx * dx + dy * dy;
+        let qb = 2.0 * (ax * dx + ay * dy);
+        let qc = ax * ax + ay * ay - self.radius * self.radius;
+        let discriminant = qb * qb - 4.0 * qa * qc;
+        if qa == 0.0 || discriminant < 0.0 {
+            return Vec::new();
+        }
+
+        let root = discriminant.sqrt();
+        [(-qb - root) / (2.0 * qa), (-qb + root) / (2.0 * qa)]
+            .into_iter()
+            .filter(|t| *t > 0.0 && *t < 1.0)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoneWaypoints {
+    /// Keep every waypoint.
+    Keep,
+    /// Drop only waypoints named "Start" or "End" inside a zone.
+    Endpoints,
+    /// Drop every waypoint inside a zone.
+    #[default]
+    All,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacyOptions {
+    pub zones: Vec<PrivacyZone>,
+    /// Also hide this many metres around the first and last track points.
+    pub endpoint_radius: Option<f64>,
+    pub waypoints: ZoneWaypoints,
+}
+
+/// Removes the parts of every track inside the privacy zones, cutting each
+/// segment where it crosses a zone boundary, and removes waypoints inside
+/// the zones according to `options.waypoints`.
+pub fn apply_privacy(route: &Route, options: &PrivacyOptions) -> Route {
+    let mut zones = options.zones.clone();
+    if let Some(radius) = options.endpoint_radius {
+        let mut points = route
+            .tracks
+            .iter()
+            .flat_map(|t| &t.segments)
+            .flat_map(|s| &s.points);
+        let first = points.next();
+        let last = points.last().or(first);
+        zones.extend(first.into_iter().chain(last).map(|p| PrivacyZone {
+            lat: p.lat,
+            lon: p.lon,
+            radius,
+        }));
+    }
+
+    let mut private = route.clone();
+    for zone in &zones {
+        for track in &mut private.tracks {
+            track.segments = track
+                .segments
+                .iter()
+                .flat_map(|s| clip_segment(s, zone, false))
+                .collect();
+        }
+    }
+    private.tracks.retain(|t| !t.segments.is_empty());
+
+    private.waypoints.retain(|w| {
+        let hidden = zones.iter().any(|z| z.contains(&w.coord));
+        let endpoint = matches!(w.name.as_deref(), Some("Start") | Some("End"));
+        match options.waypoints {
+            ZoneWaypoints::Keep => true,
+            ZoneWaypoints::Endpoints => !(hidden && endpoint),
+            ZoneWaypoints::All => !hidden,
+        }
+    });
+
+    private
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Track, TrackSegment, Waypoint};
+
+    // About 3.3 km heading east along the equator, points every ~111 m.
+    fn route() -> Route {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, 0.0),
+            "Start".to_string(),
+        ));
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, 0.001),
+            "Bakery".to_string(),
+        ));
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, 0.03),
+            "End".to_string(),
+        ));
+        let points = (0..=30)
+            .map(|i| Coordinate::new(0.0, i as f64 * 0.001))
+            .collect();
+        route.add_track(Track::new(vec![TrackSegment::new(points)]));
+        route
+    }
+
+    fn home() -> PrivacyZone {
+        PrivacyZone {
+            lat: 0.0,
+            lon: 0.0,
+            radius: 500.0,
+        }
+    }
+
+    #[test]
+    fn test_cuts_at_zone_boundary() {
+        let options = PrivacyOptions {
+            zones: vec![home()],
+            ..Default::default()
+        };
+        let private = apply_privacy(&route(), &options);
+        let points = &private.tracks[0].segments[0].points;
+
+        assert!((haversine(&home().centre(), &points[0]) - 500.0).abs() < 0.1);
+        assert_eq!(points[1].lon, 0.005);
+        assert_eq!(points.last().unwrap().lon, 0.03);
+    }
+
+    #[test]
+    fn test_zone_in_middle_splits_segment() {
+        let options = PrivacyOptions {
+            zones: vec![PrivacyZone {
+                lat: 0.0,
+                lon: 0.015,
+                radius: 200.0,
+            }],
+            ..Default::default()
+        };
+        let private = apply_privacy(&route(), &options);
+        assert_eq!(private.tracks[0].segments.len(), 2);
+    }
+
+    #[test]
+    fn test_endpoint_radius() {
+        let options = PrivacyOptions {
+            endpoint_radius: Some(300.0),
+            ..Default::default()
+        };
+        let private = apply_privacy(&route(), &options);
+        let points = &private.tracks[0].segments[0].points;
+        assert_eq!(points[1].lon, 0.003);
+        assert_eq!(points[points.len() - 2].lon, 0.027);
+        assert!(private.waypoints.is_empty());
+    }
+
+    #[test]
+    fn test_drop_only_endpoint_waypoints() {
+        let options = PrivacyOptions {
+            zones: vec![home()],
+            waypoints: ZoneWaypoints::Endpoints,
+            ..Default::default()
+        };
+        let private = apply_privacy(&route(), &options);
+        let names: Vec<_> = private
+            .waypoints
+            .iter()
+            .map(|w| w.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, vec!["Bakery", "End"]);
+    }
+
+    #[test]
+    fn test_keep_waypoints() {
+        let options = PrivacyOptions {
+            zones: vec![home()],
+            waypoints: ZoneWaypoints::Keep,
+            ..Default::default()
+        };
+        assert_eq!(apply_privacy(&route(), &options).waypoints.len(), 3);
+    }
+
+    #[test]
+    fn test_track_entirely_inside_is_removed() {
+        let options = PrivacyOptions {
+            zones: vec![PrivacyZone {
+                radius: 10_000.0,
+                ..home()
+            }],
+            ..Default::default()
+        };
+        assert!(apply_privacy(&route(), &options).tracks.is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+use crate::types::Route;
+
+/// Reverses the direction of travel: track order, segment order, point order
+/// and waypoint order are all flipped, and waypoints named "Start" and "End"
+/// swap names. Timestamps are dropped because they no longer increase along
+/// the route.
+pub fn reverse(route: &Route) -> Route {
+    let mut reversed = route.clone();
+
+    reversed.tracks.reverse();
+    for track in &mut reversed.tracks {
+        track.segments.reverse();
+        for segment in &mut track.segments {
+            segment.points.reverse();
+            for point in &mut segment.points {
+                point.time = None;
+            }
+        }
+    }
+
+    reversed.waypoints.reverse();
+    for waypoint in &mut reversed.waypoints {
+        waypoint.coord.time = None;
+        waypoint.name = match waypoint.name.as_deref() {
+            Some("Start") => Some("End".to_string()),
+            Some("End") => Some("Start".to_string()),
+            _ => waypoint.name.take(),
+        };
+    }
+
+    reversed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Coordinate, Track, TrackSegment, Waypoint};
+
+    fn route() -> Route {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, 0.0),
+            "Start".to_string(),
+        ));
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, 1.0),
+            "Cafe".to_string(),
+        ));
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, 3.0),
+            "End".to_string(),
+        ));
+        let mut timed = Coordinate::new(0.0, 0.0);
+        timed.time = Some(1000);
+        route.add_track(Track::with_name(
+            "Out".to_string(),
+            vec![
+                TrackSegment::new(vec![timed, Coordinate::new(0.0, 1.0)]),
+                TrackSegment::new(vec![Coordinate::new(0.0, 2.0), Coordinate::new(0.0, 3.0)]),
+            ],
+        ));
+        route
+    }
+
+    #[test]
+    fn test_reverse_geometry() {
+        let reversed = reverse(&route());
+        let lons: Vec<Vec<f64>> = reversed.tracks[0]
+            .segments
+            .iter()
+            .map(|s| s.points.iter().map(|p| p.lon).collect())
+            .collect();
+        assert_eq!(lons, vec![vec![3.0, 2.0], vec![1.0, 0.0]]);
+        assert!(reversed.tracks[0].segments[1].points[1].time.is_none());
+    }
+
+    #[test]
+    fn test_reverse_waypoints_and_names() {
+        let reversed = reverse(&route());
+        let names: Vec<&str> = reversed
+            .waypoints
+            .iter()
+            .map(|w| w.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, vec!["Start", "Cafe", "End"]);
+        assert_eq!(reversed.waypoints[0].coord.lon, 3.0);
+    }
+
+    #[test]
+    fn test_reverse_twice_restores_geometry() {
+        let original = route();
+        let twice = reverse(&reverse(&original));
+        assert_eq!(
+            twice.tracks[0].segments[0].points[1].lon,
+            original.tracks[0].segments[0].points[1].lon
+        );
+        assert_eq!(twice.waypoints[0].name.as_deref(), Some("Start"));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::geometry::distance::{cumulative, length, point_at};
+use crate::geometry::snap::locate_waypoints;
+use crate::types::{Coordinate, Route, Track, TrackSegment, Waypoint};
+
+/// The part of a track between two distances along it, in metres. Segments
+/// are measured end to end without the gaps between them, and the cut
+/// points are interpolated so the slice starts and ends exactly at `from`
+/// and `to`. A range that only touches a segment at its first or last point
+/// leaves that segment out, unless the range is a single point.
+pub fn slice_track(track: &Track, from: f64, to: f64) -> Track {
+    let mut sliced = Track {
+        name: track.name.clone(),
+        segments: Vec::new(),
+    };
+    let mut offset = 0.0;
+
+    for segment in &track.segments {
+        let distances = cumulative(&segment.points);
+        let end = offset + distances.last().copied().unwrap_or(0.0);
+        let (start_at, end_at) = (from.max(offset), to.min(end));
+
+        let single_point = from == to && start_at == end_at && sliced.segments.is_empty();
+        if !segment.points.is_empty() && (start_at < end_at || single_point) {
+            let mut points = vec![point_at(&segment.points, &distances, start_at - offset)];
+            points.extend(
+                segment
+                    .points
+                    .iter()
+                    .zip(&distances)
+                    .filter(|(_, &d)| d + offset > start_at && d + offset < end_at)
+                    .map(|(p, _)| p.clone()),
+            );
+            if end_at > start_at {
+                points.push(point_at(&segment.points, &distances, end_at - offset));
+            }
+            sliced.segments.push(TrackSegment::new(points));
+        }
+
+        offset = end;
+    }
+
+    sliced
+}
+
+/// Slices the route between two distances along it, counting every track
+/// and segment in order. Waypoints are kept when their closest point on the
+/// route falls inside the range.
+pub fn slice_route(route: &Route, from: f64, to: f64) -> Route {
+    let (from, to) = (from.min(to), from.max(to));
+    let mut sliced = route.clone();
+
+    let positions = locate_waypoints(route, None);
+    sliced.waypoints = route
+        .waypoints
+        .iter()
+        .zip(&positions)
+        .filter(|(_, p)| {
+            p.as_ref()
+                .is_none_or(|p| (from..=to).contains(&p.distance_along))
+        })
+        .map(|(w, _)| w.clone())
+        .collect();
+
+    let mut offset = 0.0;
+    sliced.tracks = route
+        .tracks
+        .iter()
+        .map(|track| {
+            let sliced = slice_track(track, from - offset, to - offset);
+            offset += track
+                .segments
+                .iter()
+                .map(|s| length(&s.points))
+                .sum::<f64>();
+            sliced
+        })
+        .filter(|t| !t.segments.is_empty())
+        .collect();
+
+    sliced
+}
+
+/// Slices the route between the points closest to `start` and `end`.
+pub fn slice_route_between(route: &Route, start: &Coordinate, end: &Coordinate) -> Route {
+    let mut probe = Route::new();
+    probe.tracks = route.tracks.clone();
+    probe.waypoints = vec![Waypoint::new(start.clone()), Waypoint::new(end.clone())];
+
+    match locate_waypoints(&probe, None).as_slice() {
+        [Some(a), Some(b)] => slice_route(route, a.distance_along, b.distance_along),
+        _ => route.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two segments heading east, each ~2.2 km, with points every ~1.1 km.
+    fn track() -> Track {
+        Track::new(vec![
+            TrackSegment::new(vec![
+                Coordinate::new(0.0, 0.0),
+                Coordinate::new(0.0, 0.01),
+                Coordinate::new(0.0, 0.02),
+            ]),
+            TrackSegment::new(vec![
+                Coordinate::new(0.0, 0.03),
+                Coordinate::new(0.0, 0.04),
+                Coordinate::new(0.0, 0.05),
+            ]),
+        ])
+    }
+
+    fn lons(track: &Track) -> Vec<Vec<f64>> {
+        track
+            .segments
+            .iter()
+            .map(|s| {
+                s.points
+                    .iter()
+                    .map(|p| (p.lon * 1e6).round() / 1e6)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_slice_within_segment() {
+        let step = length(&[Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01)]);
+        let sliced = slice_track(&track(), step * 0.5, step * 1.5);
+        assert_eq!(lons(&sliced), vec![vec![0.005, 0.01, 0.015]]);
+    }
+
+    #[test]
+    fn test_slice_across_segments() {
+        let step = length(&[Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01)]);
+        let sliced = slice_track(&track(), step * 1.5, step * 2.5);
+        assert_eq!(lons(&sliced), vec![vec![0.015, 0.02], vec![0.03, 0.035]]);
+    }
+
+    #[test]
+    fn test_slice_ending_on_segment_boundary() {
+        let step = length(&[Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01)]);
+        let boundary = length(&track().segments[0].points);
+
+        let sliced = slice_track(&track(), step * 0.5, boundary);
+        assert_eq!(lons(&sliced), vec![vec![0.005, 0.01, 0.02]]);
+
+        let sliced = slice_track(&track(), boundary, boundary + step * 0.5);
+        assert_eq!(lons(&sliced), vec![vec![0.03, 0.035]]);
+    }
+
+    #[test]
+    fn test_slice_single_point() {
+        let boundary = length(&track().segments[0].points);
+        let sliced = slice_track(&track(), boundary, boundary);
+        assert_eq!(lons(&sliced), vec![vec![0.02]]);
+    }
+
+    #[test]
+    fn test_slice_route_filters_waypoints() {
+        let step = length(&[Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01)]);
+        let mut route = Route::new();
+        route.add_track(track());
+        route.add_waypoint(Waypoint::new(Coordinate::new(0.0, 0.001)));
+        route.add_waypoint(Waypoint::new(Coordinate::new(0.0, 0.04)));
+
+        let sliced = slice_route(&route, step * 2.5, step * 10.0);
+        assert_eq!(sliced.waypoints.len(), 1);
+        assert_eq!(lons(&sliced.tracks[0]), vec![vec![0.035, 0.04, 0.05]]);
+    }
+
+    #[test]
+    fn test_slice_between_coordinates() {
+        let mut route = Route::new();
+        route.add_track(track());
+        let sliced = slice_route_between(
+            &route,
+            &Coordinate::new(0.001, 0.045),
+            &Coordinate::new(-0.001, 0.005),
+        );
+        assert_eq!(
+            lons(&sliced.tracks[0]),
+            vec![vec![0.005, 0.01, 0.02], vec![0.03, 0.04, 0.045]]
+        );
+    }
+
+    #[test]
+    fn test_slice_outside_range_is_empty() {
+        let mut route = Route::new();
+        route.add_track(track());
+        assert!(slice_route(&route, 1e6, 2e6).tracks.is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+use crate::geometry::distance::haversine;
+use crate::geometry::project::project;
+use crate::types::{Route, Track, TrackSegment, Waypoint};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SplitOptions {
+    pub max_points_per_segment: Option<usize>,
+    pub max_points_per_track: Option<usize>,
+    pub max_points_per_file: Option<usize>,
+    /// Maximum track length of each returned route, in metres.
+    pub max_distance_per_part: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    max_points: Option<usize>,
+    max_distance: Option<f64>,
+}
+
+/// Splits a route into parts that respect the configured limits. Adjacent
+/// pieces share their join point so the parts line up end to end.
+pub fn split_route(route: &Route, options: &SplitOptions) -> Vec<Route> {
+    let tagged: Vec<(usize, TrackSegment)> = route
+        .tracks
+        .iter()
+        .enumerate()
+        .flat_map(|(t, track)| track.segments.iter().map(move |s| (t, s.clone())))
+        .collect();
+
+    let parts = partition(
+        &tagged,
+        Limits {
+            max_points: options.max_points_per_file,
+            max_distance: options.max_distance_per_part,
+        },
+    );
+
+    let total = parts.len().max(1);
+    let mut routes: Vec<Route> = Vec::with_capacity(total);
+
+    for (index, part) in parts.into_iter().enumerate() {
+        let mut piece = Route::new();
+        piece.name = sequenced_name(route.name.as_deref(), index, total);
+
+        for (track_index, segments) in group_by_tag(part) {
+            let source = &route.tracks[track_index];
+            for track in split_track(source, &segments, options) {
+                piece.add_track(track);
+            }
+        }
+
+        routes.push(piece);
+    }
+
+    if routes.is_empty() {
+        let mut piece = route.clone();
+        piece.waypoints.clear();
+        routes.push(piece);
+    }
+
+    assign_waypoints(&mut routes, &route.waypoints);
+    routes
+}
+
+fn split_track(source: &Track, segments: &[TrackSegment], options: &SplitOptions) -> Vec<Track> {
+    let tagged: Vec<(usize, TrackSegment)> = segments.iter().map(|s| (0, s.clone())).collect();
+    let parts = partition(
+        &tagged,
+        Limits {
+            max_points: options.max_points_per_track,
+            max_distance: None,
+        },
+    );
+    let total = parts.len();
+
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(index, part)| {
+            let segments = part
+                .into_iter()
+                .flat_map(|(_, segment)| split_segment(&segment, options.max_points_per_segment))
+                .collect();
+            let mut track = Track::new(segments);
+            track.name = if total > 1 {
+                sequenced_name(
+                    Some(source.name.as_deref().unwrap_or("Track")),
+                    index,
+                    total,
+                )
+            } else {
+                source.name.clone()
+            };
+            track
+        })
+        .collect()
+}
+
+fn split_segment(segment: &TrackSegment, max_points: Option<usize>) -> Vec<TrackSegment> {
+    partition(
+        &[(0, segment.clone())],
+        Limits {
+            max_points,
+            max_distance: None,
+        },
+    )
+    .into_iter()
+    .flat_map(|part| part.into_iter().map(|(_, segment)| segment))
+    .collect()
+}
+
+/// Greedily packs tagged segments into parts. A segment that does not fit is
+/// cut, and the next part starts again from the last point of the previous one.
+fn partition(
+    segments: &[(usize, TrackSegment)],
+    limits: Limits,
+) -> Vec<Vec<(usize, TrackSegment)>> {
+    let max_points = limits.max_points.map(|m| m.max(2));
+    let mut parts = Vec::new();
+    let mut part: Vec<(usize, TrackSegment)> = Vec::new();
+    let mut count = 0;
+    let mut distance = 0.0;
+
+    for (tag, segment) in segments {
+        let mut current = Vec::new();
+
+        for point in &segment.points {
+            let step = current.last().map_or(0.0, |last| haversine(last, point));
+            let over_points = max_points.is_some_and(|m| count + 1 > m);
+            let over_distance = limits.max_distance.is_some_and(|m| distance + step > m);
+
+            if (over_points || over_distance) && (count > 1 || current.len() > 1) {
+                let join = current.last().cloned();
+                if current.len() > 1 {
+                    part.push((*tag, TrackSegment::new(std::mem::take(&mut current))));
+                }
+                parts.push(std::mem::take(&mut part));
+                current.clear();
+                count = 0;
+                distance = 0.0;
+
+                if let Some(join) = join {
+                    distance = haversine(&join, point);
+                    current.push(join);
+                    count = 1;
+                }
+            } else {
+                distance += step;
+            }
+
+            current.push(point.clone());
+            count += 1;
+        }
+
+        if !current.is_empty() {
+            part.push((*tag, TrackSegment::new(current)));
+        }
+    }
+
+    if !part.is_empty() {
+        parts.push(part);
+    }
+
+    parts
+}
+
+fn group_by_tag(part: Vec<(usize, TrackSegment)>) -> Vec<(usize, Vec<TrackSegment>)> {
+    let mut groups: Vec<(usize, Vec<TrackSegment>)> = Vec::new();
+    for (tag, segment) in part {
+        match groups.last_mut() {
+            Some((last, segments)) if *last == tag => segments.push(segment),
+            _ => groups.push((tag, vec![segment])),
+        }
+    }
+    groups
+}
+
+fn assign_waypoints(routes: &mut [Route], waypoints: &[Waypoint]) {
+    for waypoint in waypoints {
+        let nearest = routes
+            .iter()
+            .enumerate()
+            .flat_map(|(r, route)| {
+                route
+                    .tracks
+                    .iter()
+                    .flat_map(|t| t.segments.iter())
+                    .filter_map(move |s| project(&s.points, &waypoint.coord).map(|p| (r, p)))
+            })
+            .min_by(|a, b| a.1.distance_off.total_cmp(&b.1.distance_off))
+            .map_or(0, |(r, _)| r);
+
+        routes[nearest].add_waypoint(waypoint.clone());
+    }
+}
+
+fn sequenced_name(name: Option<&str>, index: usize, total: usize) -> Option<String> {
+    if total <= 1 {
+        return name.map(str::to_string);
+    }
+    Some(format!(
+        "{} ({}/{})",
+        name.unwrap_or("Route"),
+        index + 1,
+        total
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::distance::length;
+    use crate::types::Coordinate;
+
+    fn line(count: usize) -> TrackSegment {
+        TrackSegment::new(
+            (0..count)
+                .map(|i| Coordinate::new(0.0, i as f64 * 0.001))
+                .collect(),
+        )
+    }
+
+    fn route_with(count: usize) -> Route {
+        let mut route = Route::with_name("Route".to_string());
+        route.add_track(Track::with_name("Ride".to_string(), vec![line(count)]));
+        route
+    }
+
+    #[test]
+    fn test_no_limits_returns_single_route() {
+        let routes = split_route(&route_with(10), &SplitOptions::default());
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].name, Some("Route".to_string()));
+        assert_eq!(routes[0].tracks[0].segments[0].points.len(), 10);
+    }
+
+    #[test]
+    fn test_split_by_points_per_file_with_overlap() {
+        let options = SplitOptions {
+            max_points_per_file: Some(4),
+            ..SplitOptions::default()
+        };
+        let routes = split_route(&route_with(10), &options);
+        assert_eq!(routes.len(), 3);
+        assert_eq!(routes[0].name, Some("Route (1/3)".to_string()));
+        assert_eq!(routes[2].name, Some("Route (3/3)".to_string()));
+
+        let first = &routes[0].tracks[0].segments[0].points;
+        let second = &routes[1].tracks[0].segments[0].points;
+        assert_eq!(first.len(), 4);
+        assert_eq!(first.last().unwrap().lon, second[0].lon);
+
+        let total: usize = routes
+            .iter()
+            .map(|r| r.tracks[0].segments[0].points.len())
+            .sum();
+        assert_eq!(total, 10 + routes.len() - 1);
+    }
+
+    #[test]
+    fn test_split_tracks_within_file() {
+        let options = SplitOptions {
+            max_points_per_track: Some(5),
+            ..SplitOptions::default()
+        };
+        let routes = split_route(&route_with(9), &options);
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].tracks.len(), 2);
+        assert_eq!(routes[0].tracks[0].name, Some("Ride (1/2)".to_string()));
+        assert_eq!(routes[0].tracks[1].segments[0].points.len(), 5);
+    }
+
+    #[test]
+    fn test_split_segments_within_track() {
+        let options = SplitOptions {
+            max_points_per_segment: Some(3),
+            ..SplitOptions::default()
+        };
+        let routes = split_route(&route_with(7), &options);
+        assert_eq!(routes[0].tracks.len(), 1);
+        assert_eq!(routes[0].tracks[0].segments.len(), 3);
+    }
+
+    #[test]
+    fn test_split_by_distance() {
+        let route = route_with(21);
+        let total = length(&route.tracks[0].segments[0].points);
+        let options = SplitOptions {
+            max_distance_per_part: Some(total / 2.0 + 1.0),
+            ..SplitOptions::default()
+        };
+        let routes = split_route(&route, &options);
+        assert_eq!(routes.len(), 2);
+        for part in &routes {
+            assert!(length(&part.tracks[0].segments[0].points) <= total / 2.0 + 1.0);
+        }
+    }
+
+    #[test]
+    fn test_waypoints_go_to_nearest_part() {
+        let mut route = route_with(10);
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, 0.0),
+            "Start".to_string(),
+        ));
+        route.add_waypoint(Waypoint::with_name(
+            Coordinate::new(0.0, 0.009),
+            "End".to_string(),
+        ));
+
+        let options = SplitOptions {
+            max_points_per_file: Some(5),
+            ..SplitOptions::default()
+        };
+        let routes = split_route(&route, &options);
+        assert_eq!(routes[0].waypoints[0].name, Some("Start".to_string()));
+        assert_eq!(
+            routes.last().unwrap().waypoints[0].name,
+            Some("End".to_string())
+        );
+    }
+
+    #[test]
+    fn test_route_without_tracks() {
+        let mut route = Route::new();
+        route.add_waypoint(Waypoint::new(Coordinate::new(1.0, 2.0)));
+        let routes = split_route(&route, &SplitOptions::default());
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].waypoints.len(), 1);
+    }
+}
@@ -0,0 +1,289 @@
+use crate::geometry::distance::{cumulative, haversine};
+use crate::geometry::project::project;
+use crate::types::{Coordinate, Route};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TravelMode {
+    Walking,
+    Hiking,
+    #[default]
+    Cycling,
+    Driving,
+}
+
+impl TravelMode {
+    /// Typical flat-ground speed in metres per second.
+    pub fn default_speed(self) -> f64 {
+        match self {
+            TravelMode::Walking => 5.0 / 3.6,
+            TravelMode::Hiking => 4.0 / 3.6,
+            TravelMode::Cycling => 20.0 / 3.6,
+            TravelMode::Driving => 50.0 / 3.6,
+        }
+    }
+
+    /// Multiplier applied to the flat-ground speed on a given grade (rise
+    /// over run). Walking and hiking follow Tobler's hiking function;
+    /// cycling slows steadily uphill and gains up to half again downhill.
+    pub fn grade_factor(self, grade: f64) -> f64 {
+        match self {
+            TravelMode::Walking | TravelMode::Hiking => {
+                (-3.5 * ((grade + 0.05).abs() - 0.05)).exp()
+            }
+            TravelMode::Cycling if grade > 0.0 => 1.0 / (1.0 + 10.0 * grade),
+            TravelMode::Cycling => (1.0 - 5.0 * grade).min(1.5),
+            TravelMode::Driving => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimestampOptions {
+    pub travel_mode: TravelMode,
+    /// Constant speed in metres per second, overriding the travel mode's
+    /// default.
+    pub speed: Option<f64>,
+    /// Slow down uphill and speed up downhill where elevation is known.
+    pub grade_adjusted: bool,
+}
+
+impl TimestampOptions {
+    fn speed_between(&self, a: &Coordinate, b: &Coordinate, distance: f64) -> f64 {
+        let base = self
+            .speed
+            .filter(|s| *s > 0.0 && s.is_finite())
+            .unwrap_or_else(|| self.travel_mode.default_speed());
+
+        match (a.ele, b.ele) {
+            (Some(from), Some(to)) if self.grade_adjusted && distance > 0.0 => {
+                base * self.travel_mode.grade_factor((to - from) / distance)
+            }
+            _ => base,
+        }
+    }
+}
+
+/// Start time used when a route has to be timed without one being given
+/// (2000-01-01T00:00:00Z). Devices only use the differences between times.
+pub const SYNTHETIC_START: i64 = 946_684_800_000;
+
+/// Assigns a time to every track point, starting at `start` (milliseconds
+/// since the Unix epoch) and moving along the tracks in order. Waypoints get
+/// the time at which the track passes closest to them.
+pub fn add_timestamps(route: &Route, start: i64, options: &TimestampOptions) -> Route {
+    let mut timed = route.clone();
+    let mut elapsed = 0.0;
+    let mut previous: Option<Coordinate> = None;
+
+    for point in timed
+        .tracks
+        .iter_mut()
+        .flat_map(|t| t.segments.iter_mut())
+        .flat_map(|s| s.points.iter_mut())
+    {
+        if let Some(ref prev) = previous {
+            let distance = haversine(prev, point);
+            elapsed += distance / options.speed_between(prev, point, distance);
+        }
+        point.time = Some(start + (elapsed * 1000.0).round() as i64);
+        previous = Some(point.clone());
+    }
+
+    let line: Vec<Coordinate> = timed
+        .tracks
+        .iter()
+        .flat_map(|t| t.segments.iter())
+        .flat_map(|s| s.points.iter().cloned())
+        .collect();
+    for waypoint in &mut timed.waypoints {
+        if let Some(projection) = project(&line, &waypoint.coord) {
+            waypoint.coord.time = projection.point.time;
+        }
+    }
+
+    timed
+}
+
+/// The route with a time on every track point, for formats that require
+/// one. Recorded times are kept. Missing ones are interpolated by distance
+/// between the nearest timed points, or extrapolated at the default travel
+/// speed before the first and after the last. A route without any times is
+/// timed from [`SYNTHETIC_START`].
+pub fn ensure_timestamps(route: &Route) -> Route {
+    let line: Vec<Coordinate> = route
+        .tracks
+        .iter()
+        .flat_map(|t| &t.segments)
+        .flat_map(|s| s.points.iter().cloned())
+        .collect();
+    let known: Vec<usize> = (0..line.len())
+        .filter(|&i| line[i].time.is_some())
+        .collect();
+    if known.len() == line.len() {
+        return route.clone();
+    }
+    if known.is_empty() {
+        return add_timestamps(route, SYNTHETIC_START, &TimestampOptions::default());
+    }
+
+    let distances = cumulative(&line);
+    let mut timed = route.clone();
+    for (i, point) in timed
+        .tracks
+        .iter_mut()
+        .flat_map(|t| t.segments.iter_mut())
+        .flat_map(|s| s.points.iter_mut())
+        .enumerate()
+    {
+        if point.time.is_none() {
+            point.time = Some(fill_time(&line, &distances, &known, i));
+        }
+    }
+    timed
+}
+
+/// Time for the untimed point `i`, from the timed points listed in `known`.
+fn fill_time(line: &[Coordinate], distances: &[f64], known: &[usize], i: usize) -> i64 {
+    let time = |k: usize| line[k].time.unwrap_or_default();
+    let at_speed = |from: usize| {
+        let seconds = (distances[i] - distances[from]) / TravelMode::default().default_speed();
+        time(from) + (seconds * 1000.0).round() as i64
+    };
+
+    match known.partition_point(|&k| k < i) {
+        0 => at_speed(known[0]),
+        after if after == known.len() => at_speed(known[after - 1]),
+        after => {
+            let (a, b) = (known[after - 1], known[after]);
+            let span = distances[b] - distances[a];
+            let fraction = if span > 0.0 {
+                (distances[i] - distances[a]) / span
+            } else {
+                0.0
+            };
+            time(a) + ((time(b) - time(a)) as f64 * fraction).round() as i64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Track, TrackSegment, Waypoint};
+
+    fn route(elevations: &[f64]) -> Route {
+        let mut route = Route::new();
+        // Points 0.01 degrees of latitude (about 1112 m) apart.
+        let points = elevations
+            .iter()
+            .enumerate()
+            .map(|(i, &e)| Coordinate::with_elevation(i as f64 * 0.01, 0.0, e))
+            .collect();
+        route.add_track(Track::new(vec![TrackSegment::new(points)]));
+        route
+    }
+
+    fn times(route: &Route) -> Vec<i64> {
+        route
+            .tracks
+            .iter()
+            .flat_map(|t| &t.segments)
+            .flat_map(|s| &s.points)
+            .map(|p| p.time.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_constant_speed() {
+        let options = TimestampOptions {
+            speed: Some(10.0),
+            ..Default::default()
+        };
+        let timed = add_timestamps(&route(&[0.0, 0.0, 0.0]), 1_000_000, &options);
+        let times = times(&timed);
+
+        let leg = haversine(&Coordinate::new(0.0, 0.0), &Coordinate::new(0.01, 0.0));
+        assert_eq!(times[0], 1_000_000);
+        assert!((times[1] - times[0] - (leg * 100.0).round() as i64).abs() <= 1);
+        assert!((times[2] - times[1] - (leg * 100.0).round() as i64).abs() <= 1);
+    }
+
+    #[test]
+    fn test_travel_mode_default_speed() {
+        let walking = TimestampOptions {
+            travel_mode: TravelMode::Walking,
+            ..Default::default()
+        };
+        let driving = TimestampOptions {
+            travel_mode: TravelMode::Driving,
+            ..Default::default()
+        };
+        let base = route(&[0.0, 0.0]);
+        let walk = times(&add_timestamps(&base, 0, &walking))[1];
+        let drive = times(&add_timestamps(&base, 0, &driving))[1];
+        assert!((walk - drive * 10).abs() <= 10);
+    }
+
+    #[test]
+    fn test_grade_adjusted_is_slower_uphill() {
+        let options = TimestampOptions {
+            grade_adjusted: true,
+            ..Default::default()
+        };
+        let flat = times(&add_timestamps(&route(&[0.0, 0.0]), 0, &options))[1];
+        let climb = times(&add_timestamps(&route(&[0.0, 60.0]), 0, &options))[1];
+        let descent = times(&add_timestamps(&route(&[60.0, 0.0]), 0, &options))[1];
+        assert!(climb > flat);
+        assert!(descent < flat);
+    }
+
+    #[test]
+    fn test_grade_ignored_unless_enabled() {
+        let options = TimestampOptions::default();
+        let flat = times(&add_timestamps(&route(&[0.0, 0.0]), 0, &options))[1];
+        let climb = times(&add_timestamps(&route(&[0.0, 60.0]), 0, &options))[1];
+        assert_eq!(flat, climb);
+    }
+
+    #[test]
+    fn test_tobler_peaks_on_slight_descent() {
+        let mode = TravelMode::Hiking;
+        assert!(mode.grade_factor(-0.05) > mode.grade_factor(0.0));
+        assert!(mode.grade_factor(0.2) < mode.grade_factor(0.0));
+    }
+
+    #[test]
+    fn test_ensure_keeps_recorded_times() {
+        let mut base = route(&[0.0; 5]);
+        let points = &mut base.tracks[0].segments[0].points;
+        points[1].time = Some(1_000_000);
+        points[3].time = Some(1_500_000);
+
+        let times = times(&ensure_timestamps(&base));
+        assert_eq!(times[1], 1_000_000);
+        assert_eq!(times[3], 1_500_000);
+        assert!((times[2] - 1_250_000).abs() <= 1);
+
+        let leg = haversine(&Coordinate::new(0.0, 0.0), &Coordinate::new(0.01, 0.0));
+        let at_speed = (leg / TravelMode::default().default_speed() * 1000.0).round() as i64;
+        assert!((1_000_000 - times[0] - at_speed).abs() <= 1);
+        assert!((times[4] - 1_500_000 - at_speed).abs() <= 1);
+    }
+
+    #[test]
+    fn test_ensure_times_untimed_route() {
+        let timed = ensure_timestamps(&route(&[0.0, 0.0]));
+        assert_eq!(times(&timed)[0], SYNTHETIC_START);
+    }
+
+    #[test]
+    fn test_waypoint_time_from_track() {
+        let mut base = route(&[0.0, 0.0, 0.0]);
+        base.add_waypoint(Waypoint::new(Coordinate::new(0.01, 0.0001)));
+        let timed = add_timestamps(&base, 0, &TimestampOptions::default());
+        assert_eq!(timed.waypoints[0].coord.time, Some(times(&timed)[1]));
+    }
+}
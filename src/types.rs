@@ -5,11 +5,18 @@ pub struct Coordinate {
     pub lat: f64,
     pub lon: f64,
     pub ele: Option<f64>,
+    /// Milliseconds since the Unix epoch.
+    pub time: Option<i64>,
 }
 
 impl Coordinate {
     pub fn new(lat: f64, lon: f64) -> Self {
-        Self { lat, lon, ele: None }
+        Self {
+            lat,
+            lon,
+            ele: None,
+            time: None,
+        }
     }
 
     pub fn with_elevation(lat: f64, lon: f64, ele: f64) -> Self {
@@ -17,6 +24,7 @@ impl Coordinate {
             lat,
             lon,
             ele: Some(ele),
+            time: None,
         }
     }
 }
@@ -25,17 +33,28 @@ impl Coordinate {
 pub struct Waypoint {
     pub coord: Coordinate,
     pub name: Option<String>,
+    /// Symbol name shown by GPS devices, written as GPX `<sym>`.
+    pub symbol: Option<String>,
+    /// Classification of the waypoint, written as GPX `<type>`.
+    pub kind: Option<String>,
 }
 
 impl Waypoint {
     pub fn new(coord: Coordinate) -> Self {
-        Self { coord, name: None }
+        Self {
+            coord,
+            name: None,
+            symbol: None,
+            kind: None,
+        }
     }
 
     pub fn with_name(coord: Coordinate, name: String) -> Self {
         Self {
             coord,
             name: Some(name),
+            symbol: None,
+            kind: None,
         }
     }
 }
@@ -1,4 +1,4 @@
-import init, { parse_google_maps_url, parse_kml, route_to_gpx } from './pkg/routes_to_gpx.js';
+import init, { parse_google_maps_url, parse_kml, route_to_gpx, route_to_geojson } from './pkg/routes_to_gpx.js';
 
 let wasm;
 let map;
@@ -76,15 +76,12 @@ function displayRoute(route) {
 
     if (map.getSource('route')) {
         map.removeLayer('route-line');
-        map.removeSource('route');
-    }
-
-    if (map.getSource('waypoints')) {
         map.removeLayer('waypoints');
-        map.removeSource('waypoints');
+        map.removeSource('route');
     }
 
-    const coordinates = getAllCoordinates(route);
+    const geojson = JSON.parse(route_to_geojson(route, true));
+    const coordinates = getAllCoordinates(geojson);
     
     if (coordinates.length === 0) {
         showError('No coordinates found in route');
@@ -93,57 +90,32 @@ function displayRoute(route) {
 
     map.addSource('route', {
         type: 'geojson',
-        data: {
-            type: 'Feature',
-            geometry: {
-                type: 'LineString',
-                coordinates: coordinates
-            }
-        }
+        data: geojson
     });
 
     map.addLayer({
         id: 'route-line',
         type: 'line',
         source: 'route',
+        filter: ['==', ['get', 'kind'], 'track'],
         paint: {
             'line-color': '#000000',
             'line-width': 3
         }
     });
 
-    if (route.waypoints && route.waypoints.length > 0) {
-        const waypointFeatures = route.waypoints.map(wp => ({
-            type: 'Feature',
-            geometry: {
-                type: 'Point',
-                coordinates: [wp.coord.lon, wp.coord.lat]
-            },
-            properties: {
-                name: wp.name || ''
-            }
-        }));
-
-        map.addSource('waypoints', {
-            type: 'geojson',
-            data: {
-                type: 'FeatureCollection',
-                features: waypointFeatures
-            }
-        });
-
-        map.addLayer({
-            id: 'waypoints',
-            type: 'circle',
-            source: 'waypoints',
-            paint: {
-                'circle-radius': 6,
-                'circle-color': '#ffffff',
-                'circle-stroke-color': '#000000',
-                'circle-stroke-width': 2
-            }
-        });
-    }
+    map.addLayer({
+        id: 'waypoints',
+        type: 'circle',
+        source: 'route',
+        filter: ['==', ['get', 'kind'], 'waypoint'],
+        paint: {
+            'circle-radius': 6,
+            'circle-color': '#ffffff',
+            'circle-stroke-color': '#000000',
+            'circle-stroke-width': 2
+        }
+    });
 
     const bounds = new maplibregl.LngLatBounds();
     coordinates.forEach(coord => bounds.extend(coord));
@@ -154,30 +126,29 @@ function displayRoute(route) {
     });
 }
 
-function getAllCoordinates(route) {
+function getAllCoordinates(geojson) {
     const coords = [];
     
-    if (route.tracks) {
-        for (const track of route.tracks) {
-            if (track.segments) {
-                for (const segment of track.segments) {
-                    if (segment.points) {
-                        for (const point of segment.points) {
-                            coords.push([point.lon, point.lat]);
-                        }
-                    }
-                }
+    for (const feature of geojson.features) {
+        const geometry = feature.geometry;
+        if (geometry.type === 'LineString') {
+            coords.push(...geometry.coordinates);
+        } else if (geometry.type === 'MultiLineString') {
+            for (const line of geometry.coordinates) {
+                coords.push(...line);
             }
         }
     }
     
-    if (coords.length === 0 && route.waypoints) {
-        for (const wp of route.waypoints) {
-            coords.push([wp.coord.lon, wp.coord.lat]);
+    if (coords.length === 0) {
+        for (const feature of geojson.features) {
+            if (feature.geometry.type === 'Point') {
+                coords.push(feature.geometry.coordinates);
+            }
         }
     }
     
-    return coords;
+    return coords.map(coord => [coord[0], coord[1]]);
 }
 
 function handleDownload() {